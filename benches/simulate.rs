@@ -0,0 +1,190 @@
+// Copyright 2020 Tim Kilbourn. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Benchmarks of loading the input files and running simulations, run with `cargo bench`.
+//!
+//! The benchmarks run on criterion, which reports the time per run of each along with the change
+//! since the last run. Give a name after `cargo bench --` to only run the benchmarks whose names
+//! match it. The fixture is a made-up event of 32 players with 20 matches left to play.
+
+use {
+    ccls_sim::{
+        index_players, iteration_outcomes, read_matches, read_players, resolve_matches, simulate,
+        PointValues, RankBy, Scratch, SimConfig, Simulator, Tally, Tiebreakers,
+        DEFAULT_WINRATE_FLOOR,
+    },
+    criterion::{criterion_group, criterion_main, Criterion},
+};
+
+/// Number of players in the fixture
+const PLAYERS: usize = 32;
+
+/// Number of matches in the fixture
+const MATCHES: usize = 20;
+
+/// Number of matches of the fixture simulated by the exhaustive benchmark, for 2^12 outcomes
+const EXHAUSTIVE_MATCHES: usize = 12;
+
+/// Number of top ranks counted
+const TOP_RANKS: usize = 8;
+
+/// Number of samples taken of the exhaustive benchmark, fewer than criterion's default of 100
+/// since each run takes a while
+const EXHAUSTIVE_SAMPLES: usize = 10;
+
+/// the players file of the fixture, where each player has played 4 rounds against listed opponents
+fn players_csv() -> String {
+    let mut csv = String::from("name,wins,losses,opponents,opp_wins,opp_losses\n");
+    for i in 0..PLAYERS {
+        let wins = (i * 7) % 5;
+        let opponents = [1, 3, 5, 7]
+            .iter()
+            .map(|offset| format!("XX-P{:02}", (i + offset) % PLAYERS))
+            .collect::<Vec<_>>()
+            .join(";");
+        csv.push_str(&format!(
+            "XX-P{:02},{},{},{},8,8\n",
+            i,
+            wins,
+            4 - wins,
+            opponents
+        ));
+    }
+    csv
+}
+
+/// the matches file of the fixture, pairing players who haven't played each other
+fn matches_csv(count: usize) -> String {
+    let mut csv = String::from("player1,player2\n");
+    for i in 0..count {
+        let (p1, p2) = if i < PLAYERS / 2 {
+            (i, i + PLAYERS / 2)
+        } else {
+            (i - PLAYERS / 2, i + 2)
+        };
+        csv.push_str(&format!("XX-P{:02},XX-P{:02}\n", p1, p2));
+    }
+    csv
+}
+
+/// the settings of a default run, as the simulator builder sets them
+fn config() -> SimConfig {
+    SimConfig {
+        top_ranks: TOP_RANKS,
+        rank_by: RankBy::Points,
+        tiebreakers: Tiebreakers::default(),
+        trust_csv_opp: false,
+        winrate_floor: DEFAULT_WINRATE_FLOOR,
+        pooled_opp_winrate: false,
+        draws: false,
+        points: PointValues::default(),
+        pairwise: false,
+        distinct_standings: false,
+        split_ties: false,
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    let players_file = players_csv();
+    let matches_file = matches_csv(MATCHES);
+    let players = read_players(players_file.as_bytes(), 3, b',').unwrap();
+    let matches = read_matches(matches_file.as_bytes(), 3, b',').unwrap();
+
+    c.bench_function("read_players", |b| {
+        b.iter(|| read_players(players_file.as_bytes(), 3, b',').unwrap())
+    });
+    c.bench_function("read_matches", |b| {
+        b.iter(|| read_matches(matches_file.as_bytes(), 3, b',').unwrap())
+    });
+
+    let config = config();
+    let field = index_players(&players);
+    let field_matches = resolve_matches(&matches, &field, config.draws, 0.0);
+    let mut scratch = Scratch::new(field_matches.len(), &field);
+    let mut tally = Tally::new(field.len(), TOP_RANKS);
+    let mut iteration = 0;
+    c.bench_function("simulate", |b| {
+        b.iter(|| {
+            let weight = iteration_outcomes(
+                iteration,
+                &field_matches,
+                config.draws,
+                &mut scratch.outcomes,
+            );
+            simulate(
+                config,
+                &field_matches,
+                &field,
+                weight,
+                &mut scratch,
+                &mut tally,
+            );
+            iteration += 1;
+        })
+    });
+    // The same simulation with fresh buffers, which clones the whole field every time, to show
+    // what reusing the scratch space saves.
+    let mut iteration = 0;
+    c.bench_function("simulate_new_scratch", |b| {
+        b.iter(|| {
+            let mut scratch = Scratch::new(field_matches.len(), &field);
+            let weight = iteration_outcomes(
+                iteration,
+                &field_matches,
+                config.draws,
+                &mut scratch.outcomes,
+            );
+            simulate(
+                config,
+                &field_matches,
+                &field,
+                weight,
+                &mut scratch,
+                &mut tally,
+            );
+            iteration += 1;
+        })
+    });
+    // The same simulation resolving every name to its index again, as without the opponent
+    // indices cached in the field, to show what caching them saves.
+    let mut iteration = 0;
+    c.bench_function("simulate_reindexed", |b| {
+        b.iter(|| {
+            let field = index_players(&players);
+            let field_matches = resolve_matches(&matches, &field, config.draws, 0.0);
+            let mut scratch = Scratch::new(field_matches.len(), &field);
+            let weight = iteration_outcomes(
+                iteration,
+                &field_matches,
+                config.draws,
+                &mut scratch.outcomes,
+            );
+            simulate(
+                config,
+                &field_matches,
+                &field,
+                weight,
+                &mut scratch,
+                &mut tally,
+            );
+            iteration += 1;
+        })
+    });
+
+    let exhaustive_matches =
+        read_matches(matches_csv(EXHAUSTIVE_MATCHES).as_bytes(), 3, b',').unwrap();
+    let simulator = Simulator::builder(players, exhaustive_matches)
+        .top_ranks(TOP_RANKS)
+        .threads(1)
+        .build();
+    let mut group = c.benchmark_group("exhaustive");
+    group.sample_size(EXHAUSTIVE_SAMPLES);
+    group.bench_function("run_exhaustive", |b| {
+        b.iter(|| simulator.run_exhaustive().unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(simulate_benches, benches);
+criterion_main!(simulate_benches);
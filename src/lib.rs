@@ -0,0 +1,4917 @@
+// Copyright 2020 Tim Kilbourn. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Simulation engine for the CC Listener Series.
+//!
+//! Load players with [`read_players`] and matches with [`read_matches`] (or from JSON with
+//! [`read_players_json`] and [`read_matches_json`]), then build a
+//! [`Simulator`] with [`Simulator::builder`] and run it to get the [`Results`]. The `ccls` binary
+//! is a thin command-line wrapper around this library.
+
+use {
+    rayon::prelude::*,
+    serde::{Deserialize, Serialize},
+    serde_json::{value::RawValue, Value},
+    std::cmp::Ordering,
+    std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+};
+
+#[derive(Debug, Deserialize)]
+/// Player data loaded from CSV
+///
+/// Opponents are listed in any number of `opp1`, `opp2`, ... columns, and/or in a single
+/// `opponents` column separated by semicolons. Column names are matched after
+/// [`normalize_header`], and common variants are accepted as aliases.
+pub struct RawPlayer {
+    /// name of the player
+    #[serde(alias = "player")]
+    pub name: String,
+    /// number of wins by the player
+    #[serde(alias = "w", alias = "match_wins")]
+    pub wins: u32,
+    /// number of losses by the player
+    #[serde(alias = "l", alias = "match_losses")]
+    pub losses: u32,
+    /// number of draws by the player
+    #[serde(default, alias = "d", alias = "match_draws")]
+    pub draws: u32,
+    /// number of byes the player already had, which also count in `wins`
+    #[serde(default, alias = "bye")]
+    pub byes: u32,
+    /// opponents separated by semicolons
+    #[serde(default, alias = "opps")]
+    pub opponents: Option<String>,
+    /// total wins by all opponents, excluding wins against the player
+    #[serde(alias = "opponent_wins")]
+    pub opp_wins: u32,
+    /// total losses by all opponents, excluding losses against the player
+    #[serde(alias = "opponent_losses")]
+    pub opp_losses: u32,
+    /// Elo rating of the player
+    #[serde(default, alias = "elo")]
+    pub rating: Option<f64>,
+    /// number of games won by the player
+    #[serde(default, alias = "games_won")]
+    pub game_wins: u32,
+    /// number of games lost by the player
+    #[serde(default, alias = "games_lost")]
+    pub game_losses: u32,
+    /// total games won by all opponents, for the opponent game winrate with trusted opponent
+    /// records
+    #[serde(default, alias = "opponent_game_wins")]
+    pub opp_game_wins: u32,
+    /// total games lost by all opponents, for the opponent game winrate with trusted opponent
+    /// records
+    #[serde(default, alias = "opponent_game_losses")]
+    pub opp_game_losses: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Internal representation of a Player
+///
+/// Serializing a Player keeps its record and its placements, but leaves out the state of the
+/// current simulation.
+pub struct Player {
+    /// name of the player
+    pub name: String,
+    /// number of wins by the player
+    pub wins: u32,
+    /// number of losses by the player
+    pub losses: u32,
+    /// number of draws by the player
+    pub draws: u32,
+    /// number of byes the player had, which also count in `wins`; during a simulation, this
+    /// includes the simulated byes
+    #[serde(default)]
+    pub byes: u32,
+    /// total wins by all opponents, excluding wins against the player
+    pub opp_wins: u32,
+    /// total losses by all opponents, excluding losses against the player
+    pub opp_losses: u32,
+    /// list of opponents
+    pub opponents: Vec<String>,
+    /// Elo rating of the player, if known
+    pub rating: Option<f64>,
+    /// number of games won by the player
+    pub game_wins: u32,
+    /// number of games lost by the player
+    pub game_losses: u32,
+    /// total games won by all opponents
+    pub opp_game_wins: u32,
+    /// total games lost by all opponents
+    pub opp_game_losses: u32,
+    /// index of the player in the simulated field, assigned in name order
+    #[serde(skip)]
+    id: usize,
+    /// indices of the opponents in the simulated field
+    #[serde(skip)]
+    opponent_ids: Vec<usize>,
+    /// indices of the opponents beaten by the player in the current simulation
+    #[serde(skip)]
+    beat: Vec<usize>,
+    /// number of beaten opponents with the same number of wins, in the current simulation
+    #[serde(skip)]
+    head_to_head: u32,
+    /// sum of the wins of all opponents in the current simulation
+    #[serde(skip)]
+    buchholz: f64,
+    /// sum of the wins of all opponents, excluding the best and worst, in the current simulation
+    #[serde(skip)]
+    median_buchholz: f64,
+    /// the part of the wins in the recomputed opponent record that are byes not counted as whole
+    /// wins, in the current simulation; see [`compute_opponent_records`]
+    #[serde(skip)]
+    opp_bye_discount: f64,
+    /// opponent winrate of the player in the current simulation
+    #[serde(skip)]
+    opp_winrate: f64,
+    /// mean opponent winrate of the opponents in the simulated field, in the current simulation
+    #[serde(skip)]
+    opp_opp_winrate: f64,
+    /// opponent game winrate of the player in the current simulation
+    #[serde(skip)]
+    opp_game_winrate: f64,
+    /// counts of placements by the player, keyed by rank, in rank order
+    ///
+    /// When matches have win probabilities, these are expected counts and may be fractional.
+    #[serde(default)]
+    pub placements: BTreeMap<usize, f64>,
+    /// mean finishing rank of the player over all simulations, including ranks outside the top
+    /// ranks, and its standard deviation
+    #[serde(default)]
+    pub expected_rank: Option<(f64, f64)>,
+}
+
+impl Player {
+    /// create a new Player from a RawPlayer and the list of their opponents
+    pub fn new(data: RawPlayer, opponents: Vec<String>) -> Player {
+        Player {
+            name: data.name,
+            wins: data.wins,
+            losses: data.losses,
+            draws: data.draws,
+            byes: data.byes.min(data.wins),
+            opp_wins: data.opp_wins,
+            opp_losses: data.opp_losses,
+            opponents,
+            rating: data.rating,
+            game_wins: data.game_wins,
+            game_losses: data.game_losses,
+            opp_game_wins: data.opp_game_wins,
+            opp_game_losses: data.opp_game_losses,
+            id: 0,
+            opponent_ids: Vec::new(),
+            beat: Vec::new(),
+            head_to_head: 0,
+            buchholz: 0.0,
+            median_buchholz: 0.0,
+            opp_bye_discount: 0.0,
+            opp_winrate: 0.0,
+            opp_opp_winrate: 0.0,
+            opp_game_winrate: 0.0,
+            placements: BTreeMap::new(),
+            expected_rank: None,
+        }
+    }
+
+    /// add `count` final placements at `place` for the player
+    pub fn add_placements(&mut self, place: usize, count: f64) {
+        *self.placements.entry(place).or_insert(0.0) += count;
+    }
+
+    /// the chance that the player finished in the top `k` ranks, out of simulations with a total
+    /// weight of `total`
+    ///
+    /// This sums the placements at ranks 1 through `k`, so it is only complete if at least `k`
+    /// ranks were counted. Returns None if no simulations were counted.
+    pub fn top_k_probability(&self, k: usize, total: f64) -> Option<f64> {
+        if total <= 0.0 {
+            return None;
+        }
+        let count = (1..=k)
+            .filter_map(|rank| self.placements.get(&rank))
+            .fold(0.0, |sum, count| sum + count);
+        Some(count / total)
+    }
+
+    /// the 95% confidence interval of the chance that the player finished in the top `k` ranks,
+    /// estimated from `samples` random simulations
+    ///
+    /// This uses the normal approximation to the binomial distribution, clamped to between 0 and
+    /// 1, so it is only meaningful for sampled runs, where each simulation has a weight of 1.
+    /// Returns None if no simulations were counted.
+    pub fn top_k_confidence_interval(&self, k: usize, samples: f64) -> Option<(f64, f64)> {
+        let p = self.top_k_probability(k, samples)?;
+        let margin = Z_95 * (p * (1.0 - p) / samples).sqrt();
+        Some(((p - margin).max(0.0), (p + margin).min(1.0)))
+    }
+
+    /// whether the player placed in the top ranks in all, none, or some of the simulations with a
+    /// total weight of `total`
+    ///
+    /// This is only certain if the simulations covered every possible outcome. Returns None if no
+    /// simulations were counted.
+    pub fn status(&self, total: f64) -> Option<Status> {
+        if total <= 0.0 {
+            return None;
+        }
+        let placed = self.placements.values().sum::<f64>();
+        // Weighted counts are summed in a different order than the total, so allow for rounding.
+        let epsilon = total * 1e-9;
+        Some(if placed <= epsilon {
+            Status::Eliminated
+        } else if placed >= total - epsilon {
+            Status::Clinched
+        } else {
+            Status::Alive
+        })
+    }
+
+    /// reset the counters that change during a simulation to those of `base`
+    ///
+    /// This reuses the existing allocations, so that a simulation doesn't need to clone players.
+    fn reset(&mut self, base: &Player) {
+        self.wins = base.wins;
+        self.losses = base.losses;
+        self.draws = base.draws;
+        self.byes = base.byes;
+        self.opp_wins = base.opp_wins;
+        self.opp_losses = base.opp_losses;
+        self.game_wins = base.game_wins;
+        self.game_losses = base.game_losses;
+        self.opp_game_wins = base.opp_game_wins;
+        self.opp_game_losses = base.opp_game_losses;
+        self.beat.clear();
+        self.head_to_head = 0;
+        self.buchholz = 0.0;
+        self.median_buchholz = 0.0;
+        self.opp_bye_discount = 0.0;
+        self.opp_winrate = 0.0;
+        self.opp_opp_winrate = 0.0;
+        self.opp_game_winrate = 0.0;
+    }
+
+    /// add a win against the `beaten` opponents in the simulated field: one for a match between
+    /// two players, and every other player of a multiplayer pod
+    fn add_win(&mut self, beaten: impl IntoIterator<Item = usize>) {
+        self.wins = self.wins.saturating_add(1);
+        self.beat.extend(beaten);
+    }
+
+    fn add_loss(&mut self) {
+        self.losses = self.losses.saturating_add(1);
+    }
+
+    fn add_draw(&mut self) {
+        self.draws = self.draws.saturating_add(1);
+    }
+
+    /// add the games won and lost by the player in a match
+    fn add_games(&mut self, won: u32, lost: u32) {
+        self.game_wins = self.game_wins.saturating_add(won);
+        self.game_losses = self.game_losses.saturating_add(lost);
+    }
+
+    /// match points of the player, with the given points per win, bye, draw, and loss
+    ///
+    /// Byes score the points of a bye rather than those of a win.
+    pub fn points(&self, values: PointValues) -> f64 {
+        let wins = f64::from(self.wins.saturating_sub(self.byes)) * values.win;
+        let byes = f64::from(self.byes) * values.bye;
+        let draws = f64::from(self.draws) * values.draw;
+        let losses = f64::from(self.losses) * values.loss;
+        wins + byes + draws + losses
+    }
+
+    /// add a bye, after adding it as a win
+    fn add_bye(&mut self) {
+        self.byes = self.byes.saturating_add(1);
+    }
+
+    /// undo [`Player::add_bye`]
+    fn remove_bye(&mut self) {
+        self.byes = self.byes.saturating_sub(1);
+    }
+
+    fn add_opponent_win(&mut self) {
+        self.opp_wins = self.opp_wins.saturating_add(1);
+    }
+
+    fn add_opponent_loss(&mut self) {
+        self.opp_losses = self.opp_losses.saturating_add(1);
+    }
+
+    /// add the games won and lost by one of the player's opponents in a match
+    fn add_opponent_games(&mut self, won: u32, lost: u32) {
+        self.opp_game_wins = self.opp_game_wins.saturating_add(won);
+        self.opp_game_losses = self.opp_game_losses.saturating_add(lost);
+    }
+
+    /// undo [`Player::add_win`] against the `beaten` opponents
+    fn remove_win(&mut self, beaten: impl IntoIterator<Item = usize>) {
+        self.wins = self.wins.saturating_sub(1);
+        for opponent in beaten {
+            if let Some(pos) = self.beat.iter().position(|&b| b == opponent) {
+                self.beat.swap_remove(pos);
+            }
+        }
+    }
+
+    fn remove_loss(&mut self) {
+        self.losses = self.losses.saturating_sub(1);
+    }
+
+    fn remove_draw(&mut self) {
+        self.draws = self.draws.saturating_sub(1);
+    }
+
+    /// undo [`Player::add_games`]
+    fn remove_games(&mut self, won: u32, lost: u32) {
+        self.game_wins = self.game_wins.saturating_sub(won);
+        self.game_losses = self.game_losses.saturating_sub(lost);
+    }
+
+    fn remove_opponent_win(&mut self) {
+        self.opp_wins = self.opp_wins.saturating_sub(1);
+    }
+
+    fn remove_opponent_loss(&mut self) {
+        self.opp_losses = self.opp_losses.saturating_sub(1);
+    }
+
+    /// undo [`Player::add_opponent_games`]
+    fn remove_opponent_games(&mut self, won: u32, lost: u32) {
+        self.opp_game_wins = self.opp_game_wins.saturating_sub(won);
+        self.opp_game_losses = self.opp_game_losses.saturating_sub(lost);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Whether a player can still finish in the top ranks
+pub enum Status {
+    /// the player finishes in the top ranks whatever the outcomes of the remaining matches
+    Clinched,
+    /// the player cannot finish in the top ranks
+    Eliminated,
+    /// the player finishes in the top ranks with some outcomes of the remaining matches
+    Alive,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Clinched => write!(f, "clinched"),
+            Status::Eliminated => write!(f, "eliminated"),
+            Status::Alive => write!(f, "alive"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Counts of placements in a set of simulations, indexed by player index and then by rank
+///
+/// Each simulation counts with a weight, which is 1 unless match outcomes are weighted by their
+/// probabilities. Placements are counted as integers as long as every placement counts as a whole
+/// one, so that the counts of long unweighted runs stay exact; see [`Counts`].
+pub struct Tally {
+    /// number of ranks counted for each player
+    top_ranks: usize,
+    /// placement counts, with the count for rank `r` of player `id` at `id * top_ranks + r - 1`
+    counts: Counts,
+    /// weighted mean finishing rank of each player, indexed by player index
+    ///
+    /// The mean and `rank_deviations` are updated with Welford's algorithm, which stays accurate
+    /// over any number of simulations, unlike sums of ranks and of their squares.
+    rank_means: Vec<f64>,
+    /// sum of the weighted squared deviations of the finishing ranks of each player from their
+    /// mean, indexed by player index
+    rank_deviations: Vec<f64>,
+    /// weighted counts of player `a` finishing above player `b`, at `a * player_count + b`, or
+    /// empty if these pairwise counts are not tallied
+    above: Vec<f64>,
+    /// weighted counts of each order of the players in the top ranks, keyed by their indices, or
+    /// None if these are not tallied
+    standings: Option<HashMap<Vec<usize>, f64>>,
+    /// total weight of all simulations
+    total: f64,
+}
+
+impl Tally {
+    /// create an empty tally for `player_count` players and the given number of top ranks
+    pub fn new(player_count: usize, top_ranks: usize) -> Tally {
+        Tally {
+            top_ranks,
+            counts: Counts::Whole(vec![0; player_count * top_ranks]),
+            rank_means: vec![0.0; player_count],
+            rank_deviations: vec![0.0; player_count],
+            above: Vec::new(),
+            standings: None,
+            total: 0.0,
+        }
+    }
+
+    /// also tally how often each player finishes above each other player
+    ///
+    /// This takes memory and time per simulation proportional to the square of the number of
+    /// players.
+    pub fn with_pairwise(mut self) -> Tally {
+        let player_count = self.rank_means.len();
+        self.above = vec![0.0; player_count * player_count];
+        self
+    }
+
+    /// also tally how often each order of the players in the top ranks comes up
+    ///
+    /// This takes memory proportional to the number of different orders, which can be as large as
+    /// the number of simulations.
+    pub fn with_standings(mut self) -> Tally {
+        self.standings = Some(HashMap::new());
+        self
+    }
+
+    /// create an empty tally for the players of `field`, with the pairwise counts and the counts
+    /// of top-rank standings if `config` asks for them
+    fn for_config(field: &[Player], config: SimConfig) -> Tally {
+        let mut tally = Tally::new(field.len(), config.top_ranks);
+        if config.pairwise {
+            tally = tally.with_pairwise();
+        }
+        if config.distinct_standings {
+            tally = tally.with_standings();
+        }
+        tally
+    }
+
+    /// total weight of all simulations counted
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// count a simulation with the given weight
+    fn add_simulation(&mut self, weight: f64) {
+        self.total += weight;
+    }
+
+    /// count a placement at `rank` (starting from 1) by player `id`, with the given weight
+    fn add_placement(&mut self, id: usize, rank: usize, weight: f64) {
+        self.counts.add(id * self.top_ranks + rank - 1, weight);
+    }
+
+    /// count a finish at `rank` (starting from 1) by player `id`, at any rank, with the given
+    /// weight, in a simulation already counted in the total
+    fn add_rank(&mut self, id: usize, rank: usize, weight: f64) {
+        if weight <= 0.0 {
+            return;
+        }
+        let rank = rank as f64;
+        let delta = rank - self.rank_means[id];
+        self.rank_means[id] += delta * weight / self.total;
+        self.rank_deviations[id] += weight * delta * (rank - self.rank_means[id]);
+    }
+
+    /// count a simulation with the given weight, in which the players finished in the order of
+    /// the indices in `ranking`
+    ///
+    /// `ties` marks each player of `ranking` that is tied with the next one, or is empty if tied
+    /// players keep their place in `ranking`. The players of a tied group share the placements of
+    /// the ranks the group covers equally; everything else is counted from `ranking` as it is.
+    fn add_ranking(&mut self, ranking: &[usize], ties: &[bool], weight: f64) {
+        self.add_simulation(weight);
+        let top = self.top_ranks.min(ranking.len());
+        let mut start = 0;
+        while start < top {
+            let mut end = start + 1;
+            while end < ranking.len() && ties.get(end - 1) == Some(&true) {
+                end += 1;
+            }
+            let share = weight / (end - start) as f64;
+            for &id in &ranking[start..end] {
+                for rank in start..end.min(top) {
+                    self.add_placement(id, rank + 1, share);
+                }
+            }
+            start = end;
+        }
+        for (rank, &id) in ranking.iter().enumerate() {
+            self.add_rank(id, rank + 1, weight);
+        }
+        if !self.above.is_empty() {
+            let player_count = self.rank_means.len();
+            for (rank, &id) in ranking.iter().enumerate() {
+                for &below in &ranking[rank + 1..] {
+                    self.above[id * player_count + below] += weight;
+                }
+            }
+        }
+        if let Some(standings) = &mut self.standings {
+            let top = &ranking[..self.top_ranks.min(ranking.len())];
+            // Most simulations repeat a standing seen before, which needs no new key.
+            match standings.get_mut(top) {
+                Some(count) => *count += weight,
+                None => {
+                    standings.insert(top.to_vec(), weight);
+                }
+            }
+        }
+    }
+
+    /// merge the placement counts of `other` into this tally
+    pub fn merge(mut self, other: Tally) -> Tally {
+        self.counts.merge(other.counts);
+        let total = self.total + other.total;
+        if total > 0.0 {
+            for id in 0..self.rank_means.len() {
+                let delta = other.rank_means[id] - self.rank_means[id];
+                self.rank_means[id] += delta * other.total / total;
+                self.rank_deviations[id] +=
+                    other.rank_deviations[id] + delta * delta * self.total * other.total / total;
+            }
+        }
+        for (count, other_count) in self.above.iter_mut().zip(other.above) {
+            *count += other_count;
+        }
+        if let (Some(standings), Some(other_standings)) = (&mut self.standings, other.standings) {
+            for (top, count) in other_standings {
+                *standings.entry(top).or_insert(0.0) += count;
+            }
+        }
+        self.total += other.total;
+        self
+    }
+
+    /// the mean finishing rank of player `id` and its standard deviation, or None if no
+    /// simulations were counted
+    pub fn expected_rank(&self, id: usize) -> Option<(f64, f64)> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let variance = self.rank_deviations[id] / self.total;
+        Some((self.rank_means[id], variance.max(0.0).sqrt()))
+    }
+
+    /// the nonzero placement counts of player `id`, as (rank, count) pairs
+    pub fn placements(&self, id: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let (start, counts) = (id * self.top_ranks, &self.counts);
+        (start..start + self.top_ranks)
+            .map(move |i| counts.get(i))
+            .enumerate()
+            .filter(|&(_, count)| count > 0.0)
+            .map(|(rank, count)| (rank + 1, count))
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Placement counts of a [`Tally`]
+///
+/// Counts start out as integers, which count exactly however many simulations there are, unlike
+/// an f64 past 2^53. The first placement with a weight other than 1, from a simulation weighted by
+/// its probability or a placement shared between tied players, turns them into weighted counts.
+enum Counts {
+    Whole(Vec<u64>),
+    Weighted(Vec<f64>),
+}
+
+impl Counts {
+    /// the counts from a list of weighted counts, as integers if they are all whole numbers
+    fn from_weighted(counts: Vec<f64>) -> Counts {
+        // Integers above 2^53 don't convert back exactly, but weighted counts that large are also
+        // already rounded.
+        if counts
+            .iter()
+            .all(|&count| count.fract() == 0.0 && count >= 0.0)
+        {
+            Counts::Whole(counts.into_iter().map(|count| count as u64).collect())
+        } else {
+            Counts::Weighted(counts)
+        }
+    }
+
+    /// the counts as weighted counts
+    fn to_weighted(&self) -> Vec<f64> {
+        match self {
+            Counts::Whole(counts) => counts.iter().map(|&count| count as f64).collect(),
+            Counts::Weighted(counts) => counts.clone(),
+        }
+    }
+
+    /// the count at `index`
+    fn get(&self, index: usize) -> f64 {
+        match self {
+            Counts::Whole(counts) => counts[index] as f64,
+            Counts::Weighted(counts) => counts[index],
+        }
+    }
+
+    /// add a placement with the given weight to the count at `index`
+    fn add(&mut self, index: usize, weight: f64) {
+        match self {
+            Counts::Whole(counts) if weight == 1.0 => counts[index] += 1,
+            Counts::Whole(_) => {
+                *self = Counts::Weighted(self.to_weighted());
+                self.add(index, weight);
+            }
+            Counts::Weighted(counts) => counts[index] += weight,
+        }
+    }
+
+    /// add the counts of `other`, which are weighted if either one is
+    fn merge(&mut self, other: Counts) {
+        match (&mut *self, other) {
+            (Counts::Whole(counts), Counts::Whole(other)) => {
+                for (count, other_count) in counts.iter_mut().zip(other) {
+                    *count += other_count;
+                }
+            }
+            (_, other) => {
+                let mut counts = self.to_weighted();
+                for (count, other_count) in counts.iter_mut().zip(other.to_weighted()) {
+                    *count += other_count;
+                }
+                *self = Counts::Weighted(counts);
+            }
+        }
+    }
+}
+
+/// Version of the checkpoint format written by [`Checkpoint::write`]
+const CHECKPOINT_VERSION: u32 = 3;
+
+#[derive(Debug, Deserialize, Serialize)]
+/// First row of a checkpoint file
+///
+/// `total` is the weighted count of the simulations so far, which the Welford means and squared
+/// deviations of every player are over.
+struct CheckpointHeader {
+    version: u32,
+    simulations: usize,
+    next: usize,
+    seed: Option<u64>,
+    fingerprint: u64,
+    players: usize,
+    top_ranks: usize,
+    total: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+/// Row of a checkpoint file with the tally of a single player, one per player index
+///
+/// The mean rank and its sum of squared deviations are saved as the tally keeps them, so that a
+/// resumed run continues from exactly the same state.
+struct CheckpointPlayer {
+    rank_mean: f64,
+    rank_deviations: f64,
+    counts: Vec<f64>,
+}
+
+#[derive(Clone, Debug)]
+/// Progress of a run of simulations, which can be saved and resumed later
+///
+/// Simulations are independent and driven by their index, so resuming a run only needs the index
+/// of the next simulation and the placements tallied so far.
+pub struct Checkpoint {
+    /// number of simulations in the whole run
+    simulations: usize,
+    /// index of the next simulation to run
+    next: usize,
+    /// seed of a sampled run, or None for a run of the first outcomes of an exhaustive run
+    seed: Option<u64>,
+    /// fingerprint of the players and matches of the run; see [`Fingerprint`]
+    fingerprint: u64,
+    /// placements tallied by the simulations before `next`
+    tally: Tally,
+}
+
+impl Checkpoint {
+    /// number of simulations in the whole run
+    pub fn simulations(&self) -> usize {
+        self.simulations
+    }
+
+    /// index of the next simulation to run, which is also the number of simulations already run
+    pub fn next(&self) -> usize {
+        self.next
+    }
+
+    /// seed of a sampled run, or None for a run of the first outcomes of an exhaustive run
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Write the checkpoint to `w`, as CSV without a header.
+    ///
+    /// The first row has the state of the run, and each other row has the tally of one player.
+    /// Pairwise counts are not saved, so a run that tallies them can't be resumed.
+    pub fn write(&self, w: impl std::io::Write) -> csv::Result<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_writer(w);
+        let tally = &self.tally;
+        writer.serialize(CheckpointHeader {
+            version: CHECKPOINT_VERSION,
+            simulations: self.simulations,
+            next: self.next,
+            seed: self.seed,
+            fingerprint: self.fingerprint,
+            players: tally.rank_means.len(),
+            top_ranks: tally.top_ranks,
+            total: tally.total,
+        })?;
+        for id in 0..tally.rank_means.len() {
+            let start = id * tally.top_ranks;
+            writer.serialize(CheckpointPlayer {
+                rank_mean: tally.rank_means[id],
+                rank_deviations: tally.rank_deviations[id],
+                counts: (start..start + tally.top_ranks)
+                    .map(|i| tally.counts.get(i))
+                    .collect(),
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read a checkpoint written by [`Checkpoint::write`] from `rdr`.
+    ///
+    /// Returns the first error in the data instead, including rows missing for some players.
+    pub fn read(rdr: impl std::io::Read) -> csv::Result<Checkpoint> {
+        let invalid = |message: &str| {
+            csv::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                message.to_string(),
+            ))
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(rdr);
+        let mut records = reader.records();
+        let header: CheckpointHeader = match records.next() {
+            Some(record) => record?.deserialize(None)?,
+            None => return Err(invalid("empty checkpoint")),
+        };
+        if header.version != CHECKPOINT_VERSION {
+            return Err(invalid(&format!(
+                "unsupported checkpoint version {}",
+                header.version
+            )));
+        }
+        let mut tally = Tally::new(header.players, header.top_ranks);
+        tally.total = header.total;
+        let mut counts = Vec::with_capacity(header.players * header.top_ranks);
+        let mut id = 0;
+        for record in records {
+            let player: CheckpointPlayer = record?.deserialize(None)?;
+            if id >= header.players || player.counts.len() != header.top_ranks {
+                return Err(invalid(
+                    "checkpoint rows don't match its number of players and ranks",
+                ));
+            }
+            tally.rank_means[id] = player.rank_mean;
+            tally.rank_deviations[id] = player.rank_deviations;
+            counts.extend(player.counts);
+            id += 1;
+        }
+        if id != header.players || header.next > header.simulations {
+            return Err(invalid("checkpoint is truncated"));
+        }
+        tally.counts = Counts::from_weighted(counts);
+        Ok(Checkpoint {
+            simulations: header.simulations,
+            next: header.next,
+            seed: header.seed,
+            fingerprint: header.fingerprint,
+            tally,
+        })
+    }
+}
+
+/// Starting value of a 64-bit FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Multiplier of each step of a 64-bit FNV-1a hash
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A hash of the simulated field and matches, saved in checkpoints so that a run is only resumed
+/// with the same players and matches
+///
+/// This is 64-bit FNV-1a over the records, opponents, and matches as the simulations see them,
+/// which unlike the hashers of the standard library stays the same across builds.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    /// fingerprint the players of `field` and the `matches` between them
+    fn new(field: &[Player], matches: &[FieldMatch]) -> Fingerprint {
+        let mut fingerprint = Fingerprint(FNV_OFFSET);
+        fingerprint.add(field.len() as u64);
+        for player in field {
+            fingerprint.add_bytes(player.name.as_bytes());
+            for count in [
+                player.wins,
+                player.losses,
+                player.draws,
+                player.byes,
+                player.opp_wins,
+                player.opp_losses,
+                player.game_wins,
+                player.game_losses,
+                player.opp_game_wins,
+                player.opp_game_losses,
+            ] {
+                fingerprint.add(count.into());
+            }
+            fingerprint.add_option(player.rating.map(f64::to_bits));
+            fingerprint.add(player.opponent_ids.len() as u64);
+            for &opp in &player.opponent_ids {
+                fingerprint.add(opp as u64);
+            }
+        }
+        fingerprint.add(matches.len() as u64);
+        for match_ in matches {
+            for i in 0..MAX_POD_SIZE {
+                let player = if i < match_.size {
+                    match_.player(i)
+                } else {
+                    None
+                };
+                fingerprint.add_option(player.map(|id| id as u64));
+            }
+            fingerprint.add(match_.size as u64);
+            fingerprint.add(match_.bye.into());
+            fingerprint.add(match_.p1_win.to_bits());
+            fingerprint.add(match_.draw.to_bits());
+            fingerprint.add(match_.best_of.into());
+            fingerprint.add_option(match_.result.map(|result| match result {
+                Outcome::Player1Wins(lost) => u64::from(lost),
+                Outcome::Player2Wins(lost) => 1 << 32 | u64::from(lost),
+                Outcome::Draw => 2 << 32,
+                Outcome::OtherWins(i) => 3 << 32 | i as u64,
+            }));
+        }
+        fingerprint
+    }
+
+    /// add a string of bytes, after its length so that the strings stay apart
+    fn add_bytes(&mut self, bytes: &[u8]) {
+        self.add(bytes.len() as u64);
+        self.mix(bytes);
+    }
+
+    fn add(&mut self, value: u64) {
+        self.mix(&value.to_le_bytes());
+    }
+
+    fn mix(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn add_option(&mut self, value: Option<u64>) {
+        match value {
+            Some(value) => {
+                self.add(1);
+                self.add(value);
+            }
+            None => self.add(0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Settings that control how each simulation is ranked
+pub struct SimConfig {
+    /// number of top ranks to count in each simulation
+    pub top_ranks: usize,
+    /// what players are ranked by, before tiebreakers
+    pub rank_by: RankBy,
+    /// chain of tiebreakers between players with the same score, in order
+    pub tiebreakers: Tiebreakers,
+    /// whether to keep the opponent records from the player data, rather than recomputing them
+    pub trust_csv_opp: bool,
+    /// lowest winrate each opponent counts with in opponent winrates
+    pub winrate_floor: f64,
+    /// whether opponent winrates pool the records of all opponents, rather than averaging the
+    /// winrate of each opponent
+    pub pooled_opp_winrate: bool,
+    /// whether matches can be drawn
+    pub draws: bool,
+    /// points for each match result, which players are ranked by
+    pub points: PointValues,
+    /// whether to count how often each player finishes above each other player
+    pub pairwise: bool,
+    /// whether to count how often each order of the players in the top ranks comes up
+    pub distinct_standings: bool,
+    /// whether players still tied after every tiebreaker share the placements of their ranks,
+    /// rather than being placed in name order
+    pub split_ties: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Match points for each match result
+///
+/// The default is 3 points for a win, 1 for a draw, and 0 for a loss, and a bye scores as a win.
+/// Without draws, this ranks players the same as counting wins. Any result may score a fraction
+/// of a point, such as 1.5 points for a bye worth half a win.
+pub struct PointValues {
+    pub win: f64,
+    pub draw: f64,
+    pub loss: f64,
+    pub bye: f64,
+}
+
+impl PointValues {
+    /// the share of a match win that a bye counts as in match winrates: its points over those of
+    /// a win, from 0 to 1, or a whole win if wins score no points
+    fn bye_share(&self) -> f64 {
+        if self.win <= 0.0 {
+            1.0
+        } else {
+            (self.bye / self.win).clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Default for PointValues {
+    fn default() -> PointValues {
+        PointValues {
+            win: 3.0,
+            draw: 1.0,
+            loss: 0.0,
+            bye: 3.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// What players are ranked by, before tiebreakers
+pub enum RankBy {
+    /// match points; see [`PointValues`]
+    Points,
+    /// match win percentage: wins over wins and losses, or 0% for a player without either, so
+    /// that players who played fewer matches, such as after a bye, are not behind on wins
+    WinPercentage,
+}
+
+impl std::str::FromStr for RankBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "points" => Ok(RankBy::Points),
+            "winpct" => Ok(RankBy::WinPercentage),
+            _ => Err(format!(
+                "unknown ranking '{}', expected one of: points, winpct",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Tiebreaker applied between players with the same match points
+pub enum Tiebreaker {
+    /// result of the matches between the tied players in the simulated round
+    HeadToHead,
+    /// winrate of all opponents combined
+    OpponentWinrate,
+    /// sum of the match points of all opponents
+    Buchholz,
+    /// sum of the match points of all opponents, excluding the best and worst opponent
+    MedianBuchholz,
+    /// winrate of the player's own games
+    GameWinrate,
+    /// number of losses, fewest first
+    Losses,
+    /// mean opponent winrate of the player's opponents
+    OpponentOpponentWinrate,
+    /// mean game winrate of the player's opponents
+    OpponentGameWinrate,
+}
+
+impl std::str::FromStr for Tiebreaker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "h2h" => Ok(Tiebreaker::HeadToHead),
+            "oppwr" => Ok(Tiebreaker::OpponentWinrate),
+            "buchholz" => Ok(Tiebreaker::Buchholz),
+            "median-buchholz" => Ok(Tiebreaker::MedianBuchholz),
+            "gwr" => Ok(Tiebreaker::GameWinrate),
+            "losses" => Ok(Tiebreaker::Losses),
+            "oppoppwr" => Ok(Tiebreaker::OpponentOpponentWinrate),
+            "oppgwr" => Ok(Tiebreaker::OpponentGameWinrate),
+            _ => Err(format!(
+                "unknown tiebreaker '{}', expected one of: h2h, oppwr, buchholz, median-buchholz, \
+                 gwr, losses, oppoppwr, oppgwr",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Tiebreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tiebreaker::HeadToHead => write!(f, "h2h"),
+            Tiebreaker::OpponentWinrate => write!(f, "oppwr"),
+            Tiebreaker::Buchholz => write!(f, "buchholz"),
+            Tiebreaker::MedianBuchholz => write!(f, "median-buchholz"),
+            Tiebreaker::GameWinrate => write!(f, "gwr"),
+            Tiebreaker::Losses => write!(f, "losses"),
+            Tiebreaker::OpponentOpponentWinrate => write!(f, "oppoppwr"),
+            Tiebreaker::OpponentGameWinrate => write!(f, "oppgwr"),
+        }
+    }
+}
+
+/// Number of different tiebreakers, and so the length of the longest list of tiebreakers
+const TIEBREAKER_COUNT: usize = 8;
+
+/// Default chain of tiebreakers: head-to-head results, then the opponent winrate, then the
+/// opponent-of-opponent winrate
+pub const DEFAULT_TIEBREAKERS: [Tiebreaker; 3] = [
+    Tiebreaker::HeadToHead,
+    Tiebreaker::OpponentWinrate,
+    Tiebreaker::OpponentOpponentWinrate,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// An ordered chain of different tiebreakers, each applied between the players still tied after
+/// the ones before it
+///
+/// The chain has exactly the tiebreakers it is given, in order; the default is
+/// [`DEFAULT_TIEBREAKERS`]. The chain is kept in a fixed-size array, so that it can be copied
+/// along with the rest of a [`SimConfig`].
+pub struct Tiebreakers {
+    list: [Tiebreaker; TIEBREAKER_COUNT],
+    len: usize,
+}
+
+impl Tiebreakers {
+    /// create a chain of the tiebreakers in `list`, in order, which must not be empty or list a
+    /// tiebreaker more than once
+    pub fn new(list: &[Tiebreaker]) -> Result<Tiebreakers, String> {
+        if list.is_empty() {
+            return Err(String::from("no tiebreakers given"));
+        }
+        let mut tiebreakers = Tiebreakers {
+            list: [Tiebreaker::HeadToHead; TIEBREAKER_COUNT],
+            len: 0,
+        };
+        for (i, &tiebreaker) in list.iter().enumerate() {
+            if list[..i].contains(&tiebreaker) {
+                return Err(format!(
+                    "tiebreaker {} is listed more than once",
+                    tiebreaker
+                ));
+            }
+        }
+        for &tiebreaker in list {
+            // Every tiebreaker is different, so there are at most TIEBREAKER_COUNT of them.
+            tiebreakers.list[tiebreakers.len] = tiebreaker;
+            tiebreakers.len += 1;
+        }
+        Ok(tiebreakers)
+    }
+
+    /// the tiebreakers, in order
+    pub fn iter(&self) -> impl Iterator<Item = Tiebreaker> + '_ {
+        self.list[..self.len].iter().copied()
+    }
+
+    /// whether `tiebreaker` is in the chain
+    pub fn contains(&self, tiebreaker: Tiebreaker) -> bool {
+        self.iter().any(|t| t == tiebreaker)
+    }
+}
+
+impl Default for Tiebreakers {
+    /// the chain of [`DEFAULT_TIEBREAKERS`]
+    fn default() -> Tiebreakers {
+        Tiebreakers::new(&DEFAULT_TIEBREAKERS).expect("the default tiebreakers are a valid chain")
+    }
+}
+
+impl From<Tiebreaker> for Tiebreakers {
+    /// the chain of `tiebreaker` alone
+    fn from(tiebreaker: Tiebreaker) -> Tiebreakers {
+        Tiebreakers::new(&[tiebreaker]).expect("a single tiebreaker is a valid chain")
+    }
+}
+
+impl std::str::FromStr for Tiebreakers {
+    type Err = String;
+
+    /// parse a comma-separated list of tiebreakers, such as `h2h,oppwr,oppoppwr`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let list = s
+            .split(',')
+            .map(|name| name.trim().parse())
+            .collect::<Result<Vec<Tiebreaker>, _>>()?;
+        Tiebreakers::new(&list)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawMatch")]
+/// A match between two players, or a multiplayer pod of up to [`MAX_POD_SIZE`] players that one
+/// of them wins
+pub struct Match {
+    pub player1: String,
+    pub player2: String,
+    /// further players of a multiplayer pod; empty for a match between two players
+    pub others: Vec<String>,
+    /// probability that player1 wins the match (default: 0.5)
+    pub p1_win: Option<f64>,
+    /// winner of the match, if it has already been played
+    pub result: Option<String>,
+    /// number of games the match is played over, which must be odd (default: 1)
+    pub best_of: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+/// Match data loaded from CSV, before it is validated
+///
+/// Column names are matched after [`normalize_header`], and common variants are accepted as
+/// aliases.
+struct RawMatch {
+    #[serde(alias = "p1", alias = "player_1")]
+    player1: String,
+    #[serde(alias = "p2", alias = "player_2")]
+    player2: String,
+    #[serde(default, alias = "p3", alias = "player_3")]
+    player3: Option<String>,
+    #[serde(default, alias = "p4", alias = "player_4")]
+    player4: Option<String>,
+    #[serde(
+        default,
+        alias = "p1_win_prob",
+        alias = "p1_win_probability",
+        deserialize_with = "deserialize_probability"
+    )]
+    p1_win: Option<f64>,
+    #[serde(default, alias = "winner")]
+    result: Option<String>,
+    #[serde(default, alias = "bo")]
+    best_of: Option<u32>,
+}
+
+impl std::convert::TryFrom<RawMatch> for Match {
+    type Error = String;
+
+    fn try_from(mut raw: RawMatch) -> Result<Match, String> {
+        raw.player1 = normalize_name(&raw.player1);
+        raw.player2 = normalize_name(&raw.player2);
+        for name in raw
+            .player3
+            .iter_mut()
+            .chain(raw.player4.iter_mut())
+            .chain(raw.result.iter_mut())
+        {
+            *name = normalize_name(name);
+        }
+        let result = raw.result.filter(|winner| !winner.trim().is_empty());
+        if raw.player1 == BYE && raw.player2 == BYE {
+            return Err(format!("a {} needs a player", BYE));
+        }
+        if result.as_deref() == Some(BYE) {
+            return Err(format!("result '{}' is not a player", BYE));
+        }
+        let others = raw
+            .player3
+            .into_iter()
+            .chain(raw.player4)
+            .filter(|name| !name.trim().is_empty())
+            .collect::<Vec<_>>();
+        if !others.is_empty() {
+            let players = std::iter::once(&raw.player1)
+                .chain(std::iter::once(&raw.player2))
+                .chain(&others)
+                .collect::<Vec<_>>();
+            if players.iter().any(|&name| name == BYE) {
+                return Err(format!("a pod can't have a {}", BYE));
+            }
+            if let Some((_, name)) = players
+                .iter()
+                .enumerate()
+                .find(|&(i, name)| players[..i].contains(name))
+            {
+                return Err(format!("player '{}' is in the pod more than once", name));
+            }
+            if let Some(winner) = &result {
+                if !players.contains(&winner) {
+                    return Err(format!("result '{}' is not a player of the pod", winner));
+                }
+            }
+            if raw.p1_win.is_some() {
+                return Err(String::from(
+                    "a pod has no p1_win, as each of its players is equally likely to win",
+                ));
+            }
+            if raw.best_of.is_some_and(|best_of| best_of != 1) {
+                return Err(String::from("a pod is played as a single game"));
+            }
+        } else if let Some(winner) = &result {
+            if *winner != raw.player1 && *winner != raw.player2 {
+                return Err(format!(
+                    "result '{}' is neither {} nor {}",
+                    winner, raw.player1, raw.player2
+                ));
+            }
+        }
+        if let Some(best_of) = raw.best_of.filter(|&best_of| best_of % 2 == 0) {
+            return Err(format!("best_of {} is not an odd number of games", best_of));
+        }
+        Ok(Match {
+            player1: raw.player1,
+            player2: raw.player2,
+            others,
+            p1_win: raw.p1_win,
+            result,
+            best_of: raw.best_of,
+        })
+    }
+}
+
+/// Deserialize an optional probability, rejecting values that are not between 0 and 1.
+fn deserialize_probability<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let p = Option::<f64>::deserialize(deserializer)?;
+    match p {
+        Some(p) if !(0.0..=1.0).contains(&p) => Err(serde::de::Error::custom(format!(
+            "win probability {} is not between 0 and 1",
+            p
+        ))),
+        _ => Ok(p),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A match to simulate
+///
+/// A bye is a match with [`BYE`] as player2, which player1 has already won. A multiplayer pod has
+/// its further players in `others`, in the same order as player1 and player2.
+pub struct Pairing {
+    pub player1: String,
+    pub player2: String,
+    /// further players of a multiplayer pod; empty for a match between two players
+    pub others: Vec<String>,
+    /// probability that player1 wins the match, if given in the match data
+    pub p1_win: Option<f64>,
+    /// winner of the match, if it has already been played
+    pub winner: Option<String>,
+    /// number of games the match is played over, if given in the match data
+    pub best_of: Option<u32>,
+}
+
+impl Pairing {
+    /// whether the match is a bye for player1
+    pub fn is_bye(&self) -> bool {
+        self.player2 == BYE
+    }
+
+    /// whether the match is a multiplayer pod of more than two players
+    pub fn is_pod(&self) -> bool {
+        !self.others.is_empty()
+    }
+
+    /// the names of all players of the match, in order; a bye has only player1
+    pub fn players(&self) -> impl Iterator<Item = &String> {
+        let player2 = Some(&self.player2).filter(|_| !self.is_bye());
+        std::iter::once(&self.player1)
+            .chain(player2)
+            .chain(&self.others)
+    }
+}
+
+/// Largest number of players in a multiplayer pod
+pub const MAX_POD_SIZE: usize = 4;
+
+/// Name of the opponent that stands for a bye in match data: a free win for the other player of
+/// the match, who has no opponent in it. No player can be named this.
+pub const BYE: &str = "BYE";
+
+/// Order the players of a match as they are in a [`Pairing`]: the player with a bye first, or else
+/// the greater name first, so that the same match with the players swapped gets the same order.
+/// Returns whether the players were swapped.
+fn order_players(player1: String, player2: String) -> (String, String, bool) {
+    if player1 == BYE || (player2 != BYE && player1 < player2) {
+        (player2, player1, true)
+    } else {
+        (player1, player2, false)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// A match between two players of the simulated field, or a multiplayer pod
+///
+/// A pod has `size` players: the first two, then the rest in `others`. Each of them is equally
+/// likely to win it, it is played as a single game, and it can't be drawn.
+pub struct FieldMatch {
+    /// index of the first player, or None if they are not in the field
+    pub player1: Option<usize>,
+    /// index of the second player, or None if they are not in the field
+    pub player2: Option<usize>,
+    /// indices of the further players of a pod, or None if they are not in the field
+    pub others: [Option<usize>; MAX_POD_SIZE - 2],
+    /// number of players of the match: 2, or more for a pod
+    pub size: usize,
+    /// whether the match is a bye for the first player
+    pub bye: bool,
+    /// probability that the first player wins the match
+    pub p1_win: f64,
+    /// probability that the match is drawn
+    pub draw: f64,
+    /// outcome of the match, if it has already been played
+    pub result: Option<Outcome>,
+    /// number of games the match is played over, which is odd
+    pub best_of: u32,
+}
+
+impl FieldMatch {
+    /// the number of games needed to win the match
+    pub fn games_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    /// the number of possible outcomes of the match: a win by either player with each possible
+    /// number of games won by the loser, and a draw if `draws` is set
+    pub fn outcome_count(&self, draws: bool) -> usize {
+        if self.is_pod() {
+            return self.size;
+        }
+        2 * self.games_to_win() as usize + usize::from(draws)
+    }
+
+    /// whether the match is a multiplayer pod of more than two players
+    pub fn is_pod(&self) -> bool {
+        self.size > 2
+    }
+
+    /// the index in the field of the `i`th player of the match, if they are in the field
+    pub fn player(&self, i: usize) -> Option<usize> {
+        match i {
+            0 => self.player1,
+            1 => self.player2,
+            i => self.others[i - 2],
+        }
+    }
+
+    /// whether the player with index `id` in the field plays in the match
+    pub fn involves(&self, id: usize) -> bool {
+        (0..self.size).any(|i| self.player(i) == Some(id))
+    }
+
+    /// the position in the match of the winner of `outcome`, or None for a draw
+    pub fn winner(&self, outcome: Outcome) -> Option<usize> {
+        match outcome {
+            Outcome::Player1Wins(_) => Some(0),
+            Outcome::Player2Wins(_) => Some(1),
+            Outcome::OtherWins(i) => Some(i + 2),
+            Outcome::Draw => None,
+        }
+    }
+
+    /// the possible outcome at `index`, in the order they are enumerated by exhaustive runs: wins
+    /// by the first player with the loser winning 0, 1, ... games, then the same for the second
+    /// player, and then a draw; for a pod, a win by each of its players in order
+    pub fn outcome(&self, index: usize) -> Outcome {
+        if self.is_pod() {
+            return match index {
+                0 => Outcome::Player1Wins(0),
+                1 => Outcome::Player2Wins(0),
+                i => Outcome::OtherWins(i - 2),
+            };
+        }
+        let to_win = self.games_to_win() as usize;
+        if index < to_win {
+            Outcome::Player1Wins(index as u32)
+        } else if index < 2 * to_win {
+            Outcome::Player2Wins((index - to_win) as u32)
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// the probability of `outcome`
+    ///
+    /// The win probabilities are for the whole match. Given the winner, the number of games won by
+    /// the loser is distributed as if every game were 50/50.
+    pub fn probability(&self, outcome: Outcome) -> f64 {
+        if self.is_pod() {
+            return 1.0 / self.size as f64;
+        }
+        match outcome {
+            Outcome::Player1Wins(lost) => self.p1_win * self.game_split(lost),
+            Outcome::Player2Wins(lost) => (1.0 - self.p1_win - self.draw) * self.game_split(lost),
+            Outcome::Draw => self.draw,
+            Outcome::OtherWins(_) => 0.0,
+        }
+    }
+
+    /// the probability that the loser of the match won `lost` games, if every game is 50/50
+    fn game_split(&self, lost: u32) -> f64 {
+        let to_win = self.games_to_win();
+        let sequences = |lost: u32| {
+            // Number of orders of the games before the winner's final game, over all orders.
+            let games = to_win - 1 + lost;
+            let orders = (0..lost).fold(1.0, |n, k| n * f64::from(games - k) / f64::from(k + 1));
+            orders / 2f64.powi((games + 1) as i32)
+        };
+        let total = (0..to_win).map(sequences).sum::<f64>();
+        sequences(lost) / total
+    }
+
+    /// the games won by the first and the second player with `outcome`
+    ///
+    /// A drawn match splits the games evenly. Games of a pod are not counted.
+    pub fn games(&self, outcome: Outcome) -> (u32, u32) {
+        if self.is_pod() {
+            return (0, 0);
+        }
+        match outcome {
+            Outcome::Player1Wins(lost) => (self.games_to_win(), lost),
+            Outcome::Player2Wins(lost) => (lost, self.games_to_win()),
+            Outcome::Draw => (self.best_of / 2, self.best_of / 2),
+            Outcome::OtherWins(_) => (0, 0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The result of a simulated match
+///
+/// A win carries the number of games won by the loser of the match. A multiplayer pod won by one
+/// of its further players is [`Outcome::OtherWins`] with that player's index in
+/// [`FieldMatch::others`].
+pub enum Outcome {
+    Player1Wins(u32),
+    Player2Wins(u32),
+    Draw,
+    OtherWins(usize),
+}
+
+/// A small, seedable pseudo-random number generator (SplitMix64).
+///
+/// Sampled simulations only need fair coin flips, so this avoids pulling in a full RNG crate while
+/// keeping runs reproducible for a given seed.
+struct Rng {
+    state: u64,
+}
+
+/// Increment of the SplitMix64 state per generated number.
+const RNG_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+impl Rng {
+    /// Create a generator that produces the stream for `seed` starting after `offset` numbers.
+    ///
+    /// This lets each sampled simulation draw its own numbers independently of the others, while
+    /// producing the same outcomes as a single sequential generator.
+    fn at(seed: u64, offset: u64) -> Rng {
+        Rng {
+            state: seed.wrapping_add(offset.wrapping_mul(RNG_GAMMA)),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(RNG_GAMMA);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Draw a random number uniformly from [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Draw a random outcome for each match in `outcomes`, according to the win probabilities in
+    /// `matches`. Matches with a result always have it as their outcome.
+    ///
+    /// Matches over more than one game draw a second number for the games won by the loser.
+    fn sample_outcomes(&mut self, matches: &[FieldMatch], outcomes: &mut [Outcome]) {
+        for (match_, outcome) in matches.iter().zip(outcomes) {
+            if let Some(result) = match_.result {
+                *outcome = result;
+                continue;
+            }
+            let r = self.next_f64();
+            if match_.is_pod() {
+                let winner = ((r * match_.size as f64) as usize).min(match_.size - 1);
+                *outcome = match_.outcome(winner);
+                continue;
+            }
+            *outcome = if r < match_.p1_win {
+                Outcome::Player1Wins(self.sample_games_lost(match_))
+            } else if r < 1.0 - match_.draw {
+                Outcome::Player2Wins(self.sample_games_lost(match_))
+            } else {
+                Outcome::Draw
+            };
+        }
+    }
+
+    /// Draw the number of games won by the loser of `match_`, as if every game were 50/50.
+    fn sample_games_lost(&mut self, match_: &FieldMatch) -> u32 {
+        if match_.best_of <= 1 {
+            return 0;
+        }
+        let mut r = self.next_f64();
+        for lost in 0..match_.games_to_win() - 1 {
+            let p = match_.game_split(lost);
+            if r < p {
+                return lost;
+            }
+            r -= p;
+        }
+        match_.games_to_win() - 1
+    }
+}
+
+/// The number of random numbers drawn per sample for `matches`: one per match, and one more for
+/// each match over more than one game.
+fn draws_per_sample(matches: &[FieldMatch]) -> u64 {
+    matches
+        .iter()
+        .map(|match_| if match_.best_of > 1 { 2 } else { 1 })
+        .sum()
+}
+
+/// Draw the random outcomes for sample `sample` of a sampled run with the given seed.
+///
+/// Returns the weight of the simulation, which is always 1 since outcomes are drawn according to
+/// their probabilities.
+pub fn sample_outcomes(
+    seed: u64,
+    sample: usize,
+    matches: &[FieldMatch],
+    outcomes: &mut [Outcome],
+) -> f64 {
+    let offset = (sample as u64).wrapping_mul(draws_per_sample(matches));
+    Rng::at(seed, offset).sample_outcomes(matches, outcomes);
+    1.0
+}
+
+/// Strip the first `prefix_length` characters, such as a league code, from the name `s`.
+///
+/// Names with fewer characters than that are left unchanged.
+fn strip_prefix(s: String, prefix_length: usize) -> String {
+    if prefix_length == 0 {
+        return s;
+    }
+    match s.char_indices().nth(prefix_length) {
+        Some((start, _)) => String::from(&s[start..]),
+        None if s.chars().count() == prefix_length => String::new(),
+        None => s,
+    }
+}
+
+/// Compute the combined winrate of the player's opponents from their opponent record.
+///
+/// Byes of the opponents count as in their match winrates if the record was recomputed; see
+/// [`compute_opponent_records`]. Returns 0.0 if the opponents have no recorded games.
+fn pooled_opponent_winrate(p: &Player) -> f64 {
+    let games = p.opp_wins.saturating_add(p.opp_losses);
+    if games == 0 {
+        0.0
+    } else {
+        (p.opp_wins as f64 - p.opp_bye_discount) / (games as f64)
+    }
+}
+
+/// Compute the match winrate of the player: their wins over their wins and losses.
+///
+/// A bye counts as the share of a win that its points are of a win's in `config.points`; see
+/// [`bye_discount`]. Returns 0.0 if the player has no recorded wins or losses.
+fn match_winrate(p: &Player, config: SimConfig) -> f64 {
+    let games = p.wins.saturating_add(p.losses);
+    if games == 0 {
+        0.0
+    } else {
+        (p.wins as f64 - bye_discount(p, config)) / (games as f64)
+    }
+}
+
+/// The part of the wins of the player that are byes not counted as whole wins in match
+/// winrates: each bye counts as [`PointValues::bye_share`] of a win.
+fn bye_discount(p: &Player, config: SimConfig) -> f64 {
+    f64::from(p.byes) * (1.0 - config.points.bye_share())
+}
+
+/// Compute the winrate of the player's own games.
+///
+/// Returns 0.0 if the player has no recorded games.
+fn game_winrate(p: &Player) -> f64 {
+    pooled_game_winrate(p.game_wins, p.game_losses)
+}
+
+/// Compare two winrates, treating NaN as lower than any other winrate.
+fn cmp_winrate(wr1: f64, wr2: f64) -> Ordering {
+    match (wr1.is_nan(), wr2.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => wr1.partial_cmp(&wr2).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Compare two players using the selected tiebreakers, each in turn until one of them differs.
+fn tiebreak(p1: &Player, p2: &Player, tiebreakers: Tiebreakers) -> Ordering {
+    tiebreakers
+        .iter()
+        .map(|tiebreaker| tiebreak_by(p1, p2, tiebreaker))
+        .find(|&ordering| ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compare two players using `tiebreaker`.
+fn tiebreak_by(p1: &Player, p2: &Player, tiebreaker: Tiebreaker) -> Ordering {
+    match tiebreaker {
+        Tiebreaker::HeadToHead => p1.head_to_head.cmp(&p2.head_to_head),
+        Tiebreaker::OpponentWinrate => cmp_winrate(p1.opp_winrate, p2.opp_winrate),
+        Tiebreaker::Buchholz => p1.buchholz.total_cmp(&p2.buchholz),
+        Tiebreaker::MedianBuchholz => p1.median_buchholz.total_cmp(&p2.median_buchholz),
+        Tiebreaker::GameWinrate => cmp_winrate(game_winrate(p1), game_winrate(p2)),
+        Tiebreaker::Losses => p2.losses.cmp(&p1.losses),
+        Tiebreaker::OpponentOpponentWinrate => cmp_winrate(p1.opp_opp_winrate, p2.opp_opp_winrate),
+        Tiebreaker::OpponentGameWinrate => cmp_winrate(p1.opp_game_winrate, p2.opp_game_winrate),
+    }
+}
+
+/// The match points of a player, which Buchholz scores add up, and which players are ranked by
+/// unless `config.rank_by` says otherwise.
+fn score(p: &Player, config: SimConfig) -> f64 {
+    p.points(config.points)
+}
+
+/// Compare two players by what they are ranked by first, as set in `config.rank_by`.
+fn cmp_score(p1: &Player, p2: &Player, config: SimConfig) -> Ordering {
+    match config.rank_by {
+        RankBy::Points => score(p1, config).total_cmp(&score(p2, config)),
+        RankBy::WinPercentage => cmp_winrate(match_winrate(p1, config), match_winrate(p2, config)),
+    }
+}
+
+/// Order players first by score (match points, or match win percentage if set in
+/// `config.rank_by`), and then by the chain of tiebreakers in `config.tiebreakers`.
+///
+/// Players that are still tied are ordered by name, so that the ranking is deterministic. Since
+/// players are indexed in name order, this compares indices first, and only compares names for
+/// players that share an index, such as players that were never indexed.
+pub fn rank_players(p1: &Player, p2: &Player, config: SimConfig) -> Ordering {
+    cmp_score(p1, p2, config)
+        .then_with(|| tiebreak(p1, p2, config.tiebreakers))
+        .then(p2.id.cmp(&p1.id))
+        .then_with(|| p2.name.cmp(&p1.name))
+}
+
+/// Compute the head-to-head score for every player in `players`.
+///
+/// The head-to-head score is the number of opponents beaten in the current simulation that
+/// finished with the same score as the player. Between two tied players, the one who beat the
+/// other ranks higher; among more tied players, the one who beat the most of the others ranks
+/// higher.
+fn compute_head_to_head(players: &mut [Player], config: SimConfig) {
+    for id in 0..players.len() {
+        let player = &players[id];
+        let head_to_head = player
+            .beat
+            .iter()
+            .filter(|&&opp| cmp_score(&players[opp], player, config) == Ordering::Equal)
+            .count();
+        players[id].head_to_head = head_to_head as u32;
+    }
+}
+
+/// Compute the Buchholz and Median-Buchholz scores for every player from the match points of their
+/// opponents in `players`.
+///
+/// Opponents that are not in the simulated field count as zero wins. A bye is not an opponent, so
+/// it adds nothing to the score of the player who gets it, as an opponent with zero wins would,
+/// and is never the worst opponent dropped from the Median-Buchholz score; the points of the bye
+/// still counts in the scores of the player's opponents. The Median-Buchholz score only drops the
+/// best and worst opponents when the player has more than two opponents.
+///
+/// `opp_wins` is a buffer for the scores of each player's opponents, reused across simulations.
+fn compute_buchholz(players: &mut [Player], config: SimConfig, opp_wins: &mut Vec<f64>) {
+    for id in 0..players.len() {
+        opp_wins.clear();
+        opp_wins.extend(
+            players[id]
+                .opponent_ids
+                .iter()
+                .map(|&opp| score(&players[opp], config)),
+        );
+        opp_wins.sort_unstable_by(f64::total_cmp);
+        let buchholz = opp_wins.iter().sum::<f64>();
+        let median_buchholz = if opp_wins.len() > 2 {
+            opp_wins[1..opp_wins.len() - 1].iter().sum::<f64>()
+        } else {
+            buchholz
+        };
+        players[id].buchholz = buchholz;
+        players[id].median_buchholz = median_buchholz;
+    }
+}
+
+/// Recompute the opponent record of every player from the simulated records of their opponents.
+///
+/// The opponent record is the sum of the wins and losses of each listed opponent, excluding the
+/// games played against the player. This assumes that the wins and losses loaded for each player
+/// were all against their listed opponents, so that the player's losses in `field` are exactly the
+/// wins of their opponents against them, and vice versa. Simulated matches between a player and
+/// a listed opponent are excluded as well.
+///
+/// The byes of the opponents stay in their wins, but count as the same share of a win as in
+/// [`match_winrate`], so that the pooled and the mean opponent winrates agree on them.
+fn compute_opponent_records(players: &mut [Player], field: &[Player], config: SimConfig) {
+    for id in 0..players.len() {
+        let (mut opp_wins, mut opp_losses) = (0u32, 0u32);
+        let mut opp_bye_discount = 0.0;
+        for &opp in &players[id].opponent_ids {
+            let opponent = &players[opp];
+            opp_bye_discount += bye_discount(opponent, config);
+            let wins_against = opponent.beat.iter().filter(|&&beaten| beaten == id).count();
+            let losses_against = players[id]
+                .beat
+                .iter()
+                .filter(|&&beaten| beaten == opp)
+                .count();
+            opp_wins = opp_wins.saturating_add(opponent.wins.saturating_sub(wins_against as u32));
+            opp_losses =
+                opp_losses.saturating_add(opponent.losses.saturating_sub(losses_against as u32));
+        }
+        players[id].opp_wins = opp_wins.saturating_sub(field[id].losses);
+        players[id].opp_losses = opp_losses.saturating_sub(field[id].wins);
+        players[id].opp_bye_discount = opp_bye_discount;
+    }
+}
+
+/// Compute the opponent winrate for every player in `players`, starting from the records in
+/// `field`.
+///
+/// This is the arithmetic mean, over the player's opponents in the simulated field, of each
+/// opponent's match winrate in the current simulation, raised to `config.winrate_floor` if it is
+/// lower. As in real tiebreak rules, each opponent's record includes their matches against the
+/// player. Players without opponents in the field get 0.0.
+///
+/// With `config.pooled_opp_winrate`, the opponent records are recomputed instead, and the winrate
+/// is that of the combined record of all opponents. With `config.trust_csv_opp`, only the combined
+/// record is known. In both cases, the floor applies to the combined winrate.
+fn compute_opponent_winrates(players: &mut [Player], field: &[Player], config: SimConfig) {
+    if config.pooled_opp_winrate && !config.trust_csv_opp {
+        compute_opponent_records(players, field, config);
+    }
+    for id in 0..players.len() {
+        let opponents = &players[id].opponent_ids;
+        let winrate = if config.trust_csv_opp || config.pooled_opp_winrate {
+            pooled_opponent_winrate(&players[id]).max(config.winrate_floor)
+        } else if opponents.is_empty() {
+            0.0
+        } else {
+            let sum = opponents
+                .iter()
+                .map(|&opp| match_winrate(&players[opp], config).max(config.winrate_floor))
+                .sum::<f64>();
+            sum / opponents.len() as f64
+        };
+        players[id].opp_winrate = winrate;
+    }
+}
+
+/// Compute the opponent game winrate for every player in `players`.
+///
+/// This is the arithmetic mean, over the player's opponents in the simulated field, of each
+/// opponent's game winrate in the current simulation, raised to `config.winrate_floor` if it is
+/// lower, as for [`compute_opponent_winrates`]. Players without opponents in the field get 0.0.
+///
+/// With `config.pooled_opp_winrate`, the winrate is that of all games of all opponents combined.
+/// With `config.trust_csv_opp`, it is that of the opp_game_wins and opp_game_losses of the player,
+/// updated by each simulated match. In both cases, the floor applies to the combined winrate.
+fn compute_opponent_game_winrates(players: &mut [Player], config: SimConfig) {
+    for id in 0..players.len() {
+        let opponents = &players[id].opponent_ids;
+        let winrate = if config.trust_csv_opp {
+            let (won, lost) = (players[id].opp_game_wins, players[id].opp_game_losses);
+            pooled_game_winrate(won, lost).max(config.winrate_floor)
+        } else if opponents.is_empty() {
+            0.0
+        } else if config.pooled_opp_winrate {
+            let (won, lost) = opponents.iter().fold((0u32, 0u32), |(won, lost), &opp| {
+                (
+                    won.saturating_add(players[opp].game_wins),
+                    lost.saturating_add(players[opp].game_losses),
+                )
+            });
+            pooled_game_winrate(won, lost).max(config.winrate_floor)
+        } else {
+            let sum = opponents
+                .iter()
+                .map(|&opp| game_winrate(&players[opp]).max(config.winrate_floor))
+                .sum::<f64>();
+            sum / opponents.len() as f64
+        };
+        players[id].opp_game_winrate = winrate;
+    }
+}
+
+/// The winrate of `won` games out of `won + lost`, or 0.0 if there are none.
+fn pooled_game_winrate(won: u32, lost: u32) -> f64 {
+    let games = won.saturating_add(lost);
+    if games == 0 {
+        0.0
+    } else {
+        won as f64 / games as f64
+    }
+}
+
+/// Compute the opponent-of-opponent winrate for every player in `players`.
+///
+/// This is the arithmetic mean, over the player's opponents in the simulated field, of each
+/// opponent's own opponent winrate; see [`compute_opponent_winrates`]. Players without opponents in
+/// the field get 0.0. The opponent winrates must already be up to date for the current simulation.
+fn compute_opp_opp_winrates(players: &mut [Player]) {
+    for id in 0..players.len() {
+        let opponents = &players[id].opponent_ids;
+        let winrate = if opponents.is_empty() {
+            0.0
+        } else {
+            let sum = opponents
+                .iter()
+                .map(|&opp| players[opp].opp_winrate)
+                .sum::<f64>();
+            sum / opponents.len() as f64
+        };
+        players[id].opp_opp_winrate = winrate;
+    }
+}
+
+/// Build the simulated field from `players`.
+///
+/// Each player is assigned an index in name order, and their opponents are resolved to those
+/// indices. Opponents that are not in `players` are dropped. Returns the players ordered by index.
+pub fn index_players(players: &HashMap<String, Player>) -> Vec<Player> {
+    let mut field = players.values().cloned().collect::<Vec<_>>();
+    field.sort_by(|p1, p2| p1.name.cmp(&p2.name));
+    let index = field
+        .iter()
+        .enumerate()
+        .map(|(id, player)| (player.name.clone(), id))
+        .collect::<HashMap<_, _>>();
+    for (id, player) in field.iter_mut().enumerate() {
+        player.id = id;
+        player.opponent_ids = player
+            .opponents
+            .iter()
+            .filter_map(|opp| index.get(opp).copied())
+            .collect();
+    }
+    field
+}
+
+/// Default lowest winrate each opponent counts with in opponent winrates, as in real tiebreak
+/// rules
+pub const DEFAULT_WINRATE_FLOOR: f64 = 0.33;
+
+/// Quantile of the standard normal distribution for a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Odds that a player rated [`ELO_DIVISOR`] points higher than their opponent wins a match
+pub const ELO_BASE: f64 = 10.0;
+
+/// Rating difference at which the higher-rated player is [`ELO_BASE`] times as likely to win
+pub const ELO_DIVISOR: f64 = 400.0;
+
+/// Probability that a match is drawn, when draws are possible
+///
+/// This makes a draw as likely as either player winning an even match.
+const DRAW_PROBABILITY: f64 = 1.0 / 3.0;
+
+/// Compute the probability that a player rated `r1` beats a player rated `r2`.
+fn elo_win_prob(r1: f64, r2: f64) -> f64 {
+    1.0 / (1.0 + ELO_BASE.powf((r2 - r1) / ELO_DIVISOR))
+}
+
+/// Compute the probability that a player with `wins1` wins beats a player with `wins2` wins, as a
+/// logistic function of the difference in wins scaled by `prior_momentum`.
+fn momentum_win_prob(wins1: u32, wins2: u32, prior_momentum: f64) -> f64 {
+    let difference = f64::from(wins1) - f64::from(wins2);
+    1.0 / (1.0 + (-prior_momentum * difference).exp())
+}
+
+/// Resolve the players of each match to their indices in `field`.
+///
+/// Players that are not in the field resolve to None. The win probability of a match is the one
+/// given in the match data if there is one, or else computed from the Elo ratings of both players.
+/// Without either, it favors the player with more wins in `field` by `prior_momentum`, as a
+/// logistic function of the difference in wins; with a `prior_momentum` of 0, the match is 50/50.
+/// The wins are those from before the simulated round, so the probability is computed once per
+/// match rather than from the results simulated before it: an approximation that keeps every
+/// outcome independent, as exhaustive runs and their weights need.
+///
+/// If `draws` is set, each match is drawn with probability DRAW_PROBABILITY, and the win
+/// probabilities are scaled down to share the rest. Matches with a winner keep it as their result,
+/// which counts as a win without dropping a game. Matches are best of one game unless the match
+/// data says otherwise. A bye is a match won by its player against no one in the field, so it has
+/// no undecided outcome to simulate. A multiplayer pod can't be drawn.
+pub fn resolve_matches(
+    matches: &[Pairing],
+    field: &[Player],
+    draws: bool,
+    prior_momentum: f64,
+) -> Vec<FieldMatch> {
+    let draw = if draws { DRAW_PROBABILITY } else { 0.0 };
+    let index = field
+        .iter()
+        .map(|player| (player.name.as_str(), player.id))
+        .collect::<HashMap<_, _>>();
+    matches
+        .iter()
+        .map(|pairing| {
+            let player1 = index.get(pairing.player1.as_str()).copied();
+            let player2 = if pairing.is_bye() {
+                None
+            } else {
+                index.get(pairing.player2.as_str()).copied()
+            };
+            let rating1 = player1.and_then(|id| field[id].rating);
+            let rating2 = player2.and_then(|id| field[id].rating);
+            let p1_win = pairing.p1_win.unwrap_or_else(|| match (rating1, rating2) {
+                (Some(r1), Some(r2)) => elo_win_prob(r1, r2),
+                _ => match (player1, player2) {
+                    (Some(id1), Some(id2)) => {
+                        momentum_win_prob(field[id1].wins, field[id2].wins, prior_momentum)
+                    }
+                    _ => 0.5,
+                },
+            });
+            let mut others = [None; MAX_POD_SIZE - 2];
+            for (other, name) in others.iter_mut().zip(&pairing.others) {
+                *other = index.get(name.as_str()).copied();
+            }
+            let result = pairing.winner.as_ref().map(|winner| {
+                if *winner == pairing.player1 {
+                    Outcome::Player1Wins(0)
+                } else if *winner == pairing.player2 {
+                    Outcome::Player2Wins(0)
+                } else {
+                    let other = pairing.others.iter().position(|name| name == winner);
+                    Outcome::OtherWins(other.unwrap_or_default())
+                }
+            });
+            let draw = if pairing.is_pod() { 0.0 } else { draw };
+            FieldMatch {
+                player1,
+                player2,
+                others,
+                size: 2 + pairing.others.len(),
+                bye: pairing.is_bye(),
+                p1_win: p1_win * (1.0 - draw),
+                draw,
+                result,
+                best_of: pairing.best_of.unwrap_or(1),
+            }
+        })
+        .collect()
+}
+
+/// Normalize the column name `header`, so that differently written names of the same column match:
+/// surrounding whitespace is trimmed, letters are lowercased, and spaces and hyphens become
+/// underscores.
+///
+/// For example, `Opp Wins` and `opp-wins` both become `opp_wins`.
+pub fn normalize_header(header: &str) -> String {
+    header
+        .trim()
+        .chars()
+        .map(|c| match c {
+            ' ' | '-' => '_',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Normalize every column name in `headers`; see [`normalize_header`].
+fn normalize_headers(headers: &csv::StringRecord) -> csv::StringRecord {
+    headers.iter().map(normalize_header).collect()
+}
+
+/// Normalize the player name `name` from a data file, so that names written with stray spaces or
+/// quotes match: whitespace and double quotes around the name are trimmed, and each run of
+/// whitespace within it becomes a single space.
+///
+/// For example, ` "John  Smith" ` becomes `John Smith`.
+pub fn normalize_name(name: &str) -> String {
+    name.trim_matches(|c: char| c.is_whitespace() || c == '"')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Find the `oppN` columns in normalized `headers`, which may also be written `opp_N`,
+/// `opponentN`, or `opponent_N`.
+///
+/// Returns the indices of the columns, ordered by N.
+fn opponent_columns(headers: &csv::StringRecord) -> Vec<usize> {
+    let mut columns = headers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, header)| {
+            let rest = header
+                .strip_prefix("opponent")
+                .or_else(|| header.strip_prefix("opp"))?;
+            let n = rest.trim_start_matches('_').parse::<usize>().ok()?;
+            Some((n, i))
+        })
+        .collect::<Vec<_>>();
+    columns.sort_unstable();
+    columns.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Read in player data from `rdr`.
+///
+/// Fields are separated by `delimiter`, such as `b','` for CSV or `b'\t'` for TSV. The first
+/// `prefix_length` characters of each player and opponent name are stripped. Empty opponent
+/// columns are ignored, so players may have different numbers of opponents.
+/// Returns a map of Player data keyed by player name, or the first error in the data.
+pub fn read_players(
+    rdr: impl std::io::Read,
+    prefix_length: usize,
+    delimiter: u8,
+) -> csv::Result<HashMap<String, Player>> {
+    let mut players = HashMap::new();
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(rdr);
+    let headers = normalize_headers(reader.headers()?);
+    let opponent_columns = opponent_columns(&headers);
+    for row in reader.records() {
+        let player = player_from_record(&headers, &opponent_columns, &row?, prefix_length)?;
+        players.insert(player.name.clone(), player);
+    }
+    Ok(players)
+}
+
+/// Read in player data from a JSON array of objects in `rdr`, one per player.
+///
+/// Each object has the same keys as the columns of the CSV data for [`read_players`], matched the
+/// same way, and its opponents may also be an array of names. Returns the players as
+/// [`read_players`] does, or the first error in the data with the line of its object.
+pub fn read_players_json(
+    rdr: impl std::io::Read,
+    prefix_length: usize,
+) -> Result<HashMap<String, Player>, JsonError> {
+    let mut players = HashMap::new();
+    for (keys, record) in read_json_records(rdr)? {
+        let headers = normalize_headers(&keys);
+        let opponent_columns = opponent_columns(&headers);
+        let player = player_from_record(&headers, &opponent_columns, &record, prefix_length)
+            .map_err(|e| json_record_error(&keys, &e))?;
+        players.insert(player.name.clone(), player);
+    }
+    Ok(players)
+}
+
+/// Build a player from a `record` of player data with normalized `headers`, which has opponents in
+/// `opponent_columns`; see [`read_players`].
+fn player_from_record(
+    headers: &csv::StringRecord,
+    opponent_columns: &[usize],
+    record: &csv::StringRecord,
+    prefix_length: usize,
+) -> csv::Result<Player> {
+    let mut player: RawPlayer = record.deserialize(Some(headers))?;
+    player.name = strip_prefix(normalize_name(&player.name), prefix_length);
+    let listed = player.opponents.take().unwrap_or_default();
+    let opponents = opponent_columns
+        .iter()
+        .filter_map(|&i| record.get(i))
+        .chain(listed.split(';'))
+        .map(normalize_name)
+        .filter(|opp| !opp.is_empty())
+        .map(|opp| strip_prefix(opp, prefix_length))
+        .collect();
+    Ok(Player::new(player, opponents))
+}
+
+/// Read in match data from `rdr`.
+///
+/// Fields are separated by `delimiter`, and the first `prefix_length` characters of each player
+/// name are stripped, as for [`read_players`]. Match data may contain duplicates, e.g. with
+/// opponents swapped. Returns a vector of matches, with duplicates removed. A match without a win
+/// probability, a result, or a number of games takes it from a duplicate of the match, if there is
+/// one. Returns the first error in the data instead, including win probabilities that are not
+/// between 0 and 1.
+///
+/// A match against [`BYE`], which is not stripped, is a bye: it is read as already won by the other
+/// player, whatever its result and win probability.
+pub fn read_matches(
+    rdr: impl std::io::Read,
+    prefix_length: usize,
+    delimiter: u8,
+) -> csv::Result<Vec<Pairing>> {
+    let mut matches = HashMap::new();
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(rdr);
+    let headers = normalize_headers(reader.headers()?);
+    for row in reader.records() {
+        add_match_record(&mut matches, &headers, &row?, prefix_length)?;
+    }
+    let mut result = matches.into_values().collect::<Vec<_>>();
+    sort_matches(&mut result);
+    Ok(result)
+}
+
+/// Read in match data from a JSON array of objects in `rdr`, one per match.
+///
+/// Each object has the same keys as the columns of the CSV data for [`read_matches`], matched the
+/// same way. Returns the matches as [`read_matches`] does, or the first error in the data with the
+/// line of its object.
+pub fn read_matches_json(
+    rdr: impl std::io::Read,
+    prefix_length: usize,
+) -> Result<Vec<Pairing>, JsonError> {
+    let mut matches = HashMap::new();
+    for (keys, record) in read_json_records(rdr)? {
+        add_match_record(
+            &mut matches,
+            &normalize_headers(&keys),
+            &record,
+            prefix_length,
+        )
+        .map_err(|e| json_record_error(&keys, &e))?;
+    }
+    let mut result = matches.into_values().collect::<Vec<_>>();
+    sort_matches(&mut result);
+    Ok(result)
+}
+
+/// Add the match in a `record` of match data with normalized `headers` to `matches`, keyed by its
+/// players, or fill in what a duplicate of the match already in `matches` is missing; see
+/// [`read_matches`].
+fn add_match_record(
+    matches: &mut HashMap<(String, String, Vec<String>), Pairing>,
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+    prefix_length: usize,
+) -> csv::Result<()> {
+    let match_: Match = record.deserialize(Some(headers))?;
+    let strip = |name: String| {
+        if name == BYE {
+            name
+        } else {
+            strip_prefix(name, prefix_length)
+        }
+    };
+    let pairing = Pairing {
+        player1: strip(match_.player1),
+        player2: strip(match_.player2),
+        others: match_.others.into_iter().map(strip).collect(),
+        p1_win: match_.p1_win,
+        winner: match_.result.map(strip),
+        best_of: match_.best_of,
+    };
+    add_pairing(matches, pairing);
+    Ok(())
+}
+
+/// Add `pairing` to `matches`, keyed by its players in the order of [`order_pairing`], or fill in
+/// what a duplicate of the match already in `matches` is missing.
+fn add_pairing(matches: &mut HashMap<(String, String, Vec<String>), Pairing>, pairing: Pairing) {
+    let pairing = order_pairing(pairing);
+    let key = (
+        pairing.player1.clone(),
+        pairing.player2.clone(),
+        pairing.others.clone(),
+    );
+    match matches.entry(key) {
+        Entry::Vacant(entry) => {
+            entry.insert(pairing);
+        }
+        Entry::Occupied(mut entry) => {
+            let entry = entry.get_mut();
+            if entry.p1_win.is_none() {
+                entry.p1_win = pairing.p1_win;
+            }
+            if entry.winner.is_none() {
+                entry.winner = pairing.winner;
+            }
+            if entry.best_of.is_none() {
+                entry.best_of = pairing.best_of;
+            }
+        }
+    }
+}
+
+/// Order the players of `pairing` as [`order_players`] does, turning its win probability around
+/// if they are swapped. A bye is won by its player, without a win probability.
+///
+/// The players of a pod are equally likely to win, so their order only needs to be the same for
+/// every duplicate of the pod: greatest name first.
+fn order_pairing(mut pairing: Pairing) -> Pairing {
+    if pairing.is_pod() {
+        let mut players = std::iter::once(pairing.player1)
+            .chain(std::iter::once(pairing.player2))
+            .chain(pairing.others)
+            .collect::<Vec<_>>();
+        players.sort_unstable_by(|a, b| b.cmp(a));
+        pairing.others = players.split_off(2);
+        pairing.player2 = players.pop().unwrap_or_default();
+        pairing.player1 = players.pop().unwrap_or_default();
+        return pairing;
+    }
+    let (player1, player2, swapped) = order_players(pairing.player1, pairing.player2);
+    if player2 == BYE {
+        pairing.winner = Some(player1.clone());
+        pairing.p1_win = None;
+    } else if swapped {
+        pairing.p1_win = pairing.p1_win.map(|p| 1.0 - p);
+    }
+    pairing.player1 = player1;
+    pairing.player2 = player2;
+    pairing
+}
+
+#[derive(Clone, Debug, Default)]
+/// The spellings of the names of the players, to match names written in a different case
+///
+/// Each spelling is keyed by the name in lowercase; see [`fold_player_case`].
+pub struct NameCase {
+    spellings: HashMap<String, String>,
+}
+
+impl NameCase {
+    /// the name `name`, in any case, as spelled by the player of that name, or `name` itself if
+    /// there is no such player; [`BYE`] is always kept as it is
+    pub fn spelling(&self, name: String) -> String {
+        if name == BYE {
+            return name;
+        }
+        match self.spellings.get(&name.to_lowercase()) {
+            Some(spelling) => spelling.clone(),
+            None => name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Two players whose names only differ in case, which [`fold_player_case`] can't tell apart
+pub struct CaseCollision {
+    /// name of the first player, in name order
+    pub first: String,
+    /// name of the second player
+    pub second: String,
+}
+
+/// Fold the names of `players` that only differ in case onto a single spelling, so that names are
+/// matched regardless of case.
+///
+/// Each player's opponents are spelled as the names of the players. Returns the spellings of the
+/// names, or the first two players in name order whose names are the same in lowercase, since
+/// they can't both be matched regardless of case.
+pub fn fold_player_case(players: &mut HashMap<String, Player>) -> Result<NameCase, CaseCollision> {
+    let mut names = players.keys().cloned().collect::<Vec<_>>();
+    names.sort_unstable();
+    let mut case = NameCase::default();
+    for name in names {
+        match case.spellings.entry(name.to_lowercase()) {
+            Entry::Vacant(entry) => {
+                entry.insert(name);
+            }
+            Entry::Occupied(entry) => {
+                return Err(CaseCollision {
+                    first: entry.get().clone(),
+                    second: name,
+                });
+            }
+        }
+    }
+    for player in players.values_mut() {
+        let opponents = std::mem::take(&mut player.opponents);
+        player.opponents = opponents
+            .into_iter()
+            .map(|opponent| case.spelling(opponent))
+            .collect();
+    }
+    Ok(case)
+}
+
+/// Spell the names of the players of `matches` as in `case`, so that they are matched regardless
+/// of case, and merge the matches that become duplicates of each other as [`read_matches`] does.
+/// Returns the matches sorted as by [`read_matches`].
+pub fn fold_match_case(matches: Vec<Pairing>, case: &NameCase) -> Vec<Pairing> {
+    let mut folded = HashMap::new();
+    for pairing in matches {
+        let pairing = Pairing {
+            player1: case.spelling(pairing.player1),
+            player2: case.spelling(pairing.player2),
+            others: pairing
+                .others
+                .into_iter()
+                .map(|name| case.spelling(name))
+                .collect(),
+            winner: pairing.winner.map(|name| case.spelling(name)),
+            ..pairing
+        };
+        add_pairing(&mut folded, pairing);
+    }
+    let mut result = folded.into_values().collect::<Vec<_>>();
+    sort_matches(&mut result);
+    result
+}
+
+#[derive(Debug)]
+/// An error in JSON input
+pub enum JsonError {
+    /// reading the input failed
+    Io(std::io::Error),
+    /// the input is not an array of objects with valid data, at the given line
+    Invalid { line: u64, message: String },
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::Io(e) => write!(f, "{}", e),
+            JsonError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl JsonError {
+    /// the error `e` from serde_json in JSON input that starts at line `first_line` of the file
+    fn from_serde(e: serde_json::Error, first_line: u64) -> JsonError {
+        if e.is_io() {
+            return JsonError::Io(e.into());
+        }
+        // serde_json appends the position to the message, which is given as the line instead.
+        let message = e.to_string();
+        let position = format!(" at line {} column {}", e.line(), e.column());
+        JsonError::Invalid {
+            line: first_line + e.line().saturating_sub(1) as u64,
+            message: message
+                .strip_suffix(&position)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+/// Read the JSON array of objects in `rdr` with serde_json, as a header and a record for each
+/// object, so that JSON input is deserialized by the same code, with the same column names and
+/// checks, as CSV input.
+///
+/// The header of each object lists its keys and the record their values. Null is an empty field,
+/// and arrays of scalars are joined with semicolons, as in the `opponents` column of CSV input.
+/// Each record has the line where its object starts as its position.
+fn read_json_records(
+    mut rdr: impl std::io::Read,
+) -> Result<Vec<(csv::StringRecord, csv::StringRecord)>, JsonError> {
+    let mut input = String::new();
+    rdr.read_to_string(&mut input).map_err(JsonError::Io)?;
+    let objects: Vec<&RawValue> =
+        serde_json::from_str(&input).map_err(|e| JsonError::from_serde(e, 1))?;
+    let mut records = Vec::with_capacity(objects.len());
+    for object in objects {
+        // Each raw object borrows its text from the input, which gives where it starts.
+        let offset = object.get().as_ptr() as usize - input.as_ptr() as usize;
+        let line = 1 + input[..offset].matches('\n').count() as u64;
+        let fields: serde_json::Map<String, Value> =
+            serde_json::from_str(object.get()).map_err(|e| JsonError::from_serde(e, line))?;
+        let mut keys = csv::StringRecord::new();
+        let mut record = csv::StringRecord::new();
+        for (key, value) in fields {
+            let field = match value {
+                Value::Array(items) => items
+                    .into_iter()
+                    .filter(|item| !item.is_null())
+                    .map(json_scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .map(|items| items.join(";")),
+                value => json_scalar(value),
+            };
+            let field = field.ok_or_else(|| JsonError::Invalid {
+                line,
+                message: format!("\"{}\" is nested too deeply", key),
+            })?;
+            keys.push_field(&key);
+            record.push_field(&field);
+        }
+        let mut position = csv::Position::new();
+        position.set_line(line);
+        record.set_position(Some(position));
+        records.push((keys, record));
+    }
+    Ok(records)
+}
+
+/// The text of the JSON scalar `value` as a field of a record, which is empty for null, or None
+/// for an array or object.
+fn json_scalar(value: Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Describe the error `e` in a record read from JSON with the given `keys`, naming the key of the
+/// offending field rather than its column.
+fn json_record_error(keys: &csv::StringRecord, e: &csv::Error) -> JsonError {
+    let message = match e.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => {
+            match err.field().and_then(|i| keys.get(i as usize)) {
+                Some(key) => format!("\"{}\": {}", key, err.kind()),
+                None => err.kind().to_string(),
+            }
+        }
+        _ => e.to_string(),
+    };
+    JsonError::Invalid {
+        line: e.position().map_or(0, |pos| pos.line()),
+        message,
+    }
+}
+
+/// Sort `matches` by their players.
+///
+/// The order of the matches determines the order of exhaustive simulations, so sorting them makes
+/// runs of a subset of the simulations deterministic.
+fn sort_matches(matches: &mut [Pairing]) {
+    matches.sort_unstable_by(|m1, m2| {
+        (&m1.player1, &m1.player2, &m1.others).cmp(&(&m2.player1, &m2.player2, &m2.others))
+    });
+}
+
+/// Add an undecided match between every two of the players named in `group` to `matches`, as in
+/// a round-robin.
+///
+/// Pairs of players that already have a match in `matches`, with the players in either order, keep
+/// that match, and repeated names are only paired once. The matches are sorted as by
+/// [`read_matches`] afterwards. Returns the number of matches added.
+pub fn add_round_robin(matches: &mut Vec<Pairing>, group: &[String]) -> usize {
+    let mut group = group.iter().collect::<Vec<_>>();
+    group.sort_unstable();
+    group.dedup();
+    let pairs = group
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &player1)| {
+            group[i + 1..]
+                .iter()
+                .map(move |&player2| (player1.clone(), player2.clone()))
+        })
+        .collect::<Vec<_>>();
+    add_pairings(matches, &pairs)
+}
+
+/// Add an undecided match for each pair of player names in `pairs` to `matches`.
+///
+/// A pair with [`BYE`] adds a bye for the other player instead. Pairs of players that already have
+/// a match in `matches`, with the players in either order, keep that match; playing in the same
+/// multiplayer pod is not a match between them. The matches are sorted as by [`read_matches`]
+/// afterwards. Returns the number of matches added.
+pub fn add_pairings(matches: &mut Vec<Pairing>, pairs: &[(String, String)]) -> usize {
+    let mut paired = matches
+        .iter()
+        .filter(|pairing| !pairing.is_pod())
+        .map(|pairing| (pairing.player1.clone(), pairing.player2.clone()))
+        .collect::<HashSet<_>>();
+    let before = matches.len();
+    for (player1, player2) in pairs {
+        let (player1, player2, _) = order_players(player1.clone(), player2.clone());
+        if player1 != player2 && paired.insert((player1.clone(), player2.clone())) {
+            let winner = if player2 == BYE {
+                Some(player1.clone())
+            } else {
+                None
+            };
+            matches.push(Pairing {
+                player1,
+                player2,
+                others: Vec::new(),
+                p1_win: None,
+                winner,
+                best_of: None,
+            });
+        }
+    }
+    sort_matches(matches);
+    matches.len() - before
+}
+
+/// Maximum number of pairings tried by [`swiss_pairings`] before it gives up on avoiding rematches
+const SWISS_SEARCH_LIMIT: usize = 100_000;
+
+/// Pair the next Swiss round from the current standings of `players`, ranked by match points.
+///
+/// Each player, from the top of the standings down, is paired with the highest-ranked player left
+/// that they have not played yet according to their opponents, so that players meet others with
+/// the same or the nearest record. If that leaves players who can only be paired in a rematch,
+/// earlier pairings are revisited; if there is no way to avoid rematches, players are paired in
+/// standings order regardless. With an odd number of players, the lowest-ranked player gets a
+/// bye, paired with [`BYE`]. Players with the same match points are ordered by name, so the
+/// pairings are deterministic.
+pub fn swiss_pairings(
+    players: &HashMap<String, Player>,
+    points: PointValues,
+) -> Vec<(String, String)> {
+    let mut standings = players.values().collect::<Vec<_>>();
+    standings.sort_by(|p1, p2| {
+        p2.points(points)
+            .total_cmp(&p1.points(points))
+            .then_with(|| p1.name.cmp(&p2.name))
+    });
+    let bye = if standings.len() % 2 == 1 {
+        standings.pop()
+    } else {
+        None
+    };
+    let mut paired = vec![false; standings.len()];
+    let mut pairs = Vec::new();
+    let mut limit = SWISS_SEARCH_LIMIT;
+    if !pair_without_rematches(&standings, &mut paired, &mut pairs, &mut limit) {
+        pairs = (0..standings.len())
+            .step_by(2)
+            .map(|i| (i, i + 1))
+            .collect();
+    }
+    pairs
+        .into_iter()
+        .map(|(p1, p2)| (standings[p1].name.clone(), standings[p2].name.clone()))
+        .chain(bye.map(|player| (player.name.clone(), BYE.to_string())))
+        .collect()
+}
+
+/// Pair the players of `standings` that are not `paired` yet without rematches, adding their
+/// indices to `pairs`, and return whether that succeeded. Gives up once `limit` pairings have been
+/// tried.
+fn pair_without_rematches(
+    standings: &[&Player],
+    paired: &mut [bool],
+    pairs: &mut Vec<(usize, usize)>,
+    limit: &mut usize,
+) -> bool {
+    let first = match paired.iter().position(|&paired| !paired) {
+        Some(first) => first,
+        None => return true,
+    };
+    paired[first] = true;
+    for other in first + 1..standings.len() {
+        let (player1, player2) = (standings[first], standings[other]);
+        if paired[other]
+            || player1.opponents.contains(&player2.name)
+            || player2.opponents.contains(&player1.name)
+        {
+            continue;
+        }
+        if *limit == 0 {
+            break;
+        }
+        *limit -= 1;
+        paired[other] = true;
+        pairs.push((first, other));
+        if pair_without_rematches(standings, paired, pairs, limit) {
+            return true;
+        }
+        paired[other] = false;
+        pairs.pop();
+    }
+    paired[first] = false;
+    false
+}
+
+/// Find the players named in `matches` that are not in `players`.
+///
+/// Matches against unknown players still count for the known player, but the unknown player is
+/// not ranked, which usually means the two files disagree on how names are written. [`BYE`] is not
+/// a player, so it is never unknown. Returns the unknown names in name order, without duplicates.
+pub fn unknown_players(players: &HashMap<String, Player>, matches: &[Pairing]) -> Vec<String> {
+    let mut unknown = matches
+        .iter()
+        .flat_map(Pairing::players)
+        .filter(|name| !players.contains_key(name.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    unknown.sort_unstable();
+    unknown.dedup();
+    unknown
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A player whose opponent record disagrees with the records of their opponents
+pub struct OpponentRecordMismatch {
+    /// name of the player
+    pub name: String,
+    /// opponent wins loaded for the player
+    pub opp_wins: u32,
+    /// opponent losses loaded for the player
+    pub opp_losses: u32,
+    /// wins of the player's opponents, excluding their wins against the player
+    pub expected_wins: u32,
+    /// losses of the player's opponents, excluding their losses against the player
+    pub expected_losses: u32,
+}
+
+/// Check the opponent record of every player in `players` against the records of their listed
+/// opponents.
+///
+/// As when pooling opponent records with [`SimulatorBuilder::pooled_opp_winrate`], the wins and
+/// losses of each player are assumed to be all against their listed opponents, so the opponents'
+/// wins excluding those against the player are their total wins less the player's losses, and the
+/// same for their losses. Players with an opponent that is not in `players` can't be checked and
+/// are skipped. Returns the players whose loaded opponent record differs, in name order.
+pub fn check_opponent_records(players: &HashMap<String, Player>) -> Vec<OpponentRecordMismatch> {
+    let mut mismatches = players
+        .values()
+        .filter_map(|player| {
+            let (mut wins, mut losses) = (0u32, 0u32);
+            for name in &player.opponents {
+                let opponent = players.get(name)?;
+                wins = wins.saturating_add(opponent.wins);
+                losses = losses.saturating_add(opponent.losses);
+            }
+            let expected_wins = wins.saturating_sub(player.losses);
+            let expected_losses = losses.saturating_sub(player.wins);
+            if (player.opp_wins, player.opp_losses) == (expected_wins, expected_losses) {
+                return None;
+            }
+            Some(OpponentRecordMismatch {
+                name: player.name.clone(),
+                opp_wins: player.opp_wins,
+                opp_losses: player.opp_losses,
+                expected_wins,
+                expected_losses,
+            })
+        })
+        .collect::<Vec<_>>();
+    mismatches.sort_unstable_by(|m1, m2| m1.name.cmp(&m2.name));
+    mismatches
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A player whose listed opponents disagree with the opponents listed by other players, or with
+/// the matches
+pub struct OpponentListMismatch {
+    /// name of the player
+    pub name: String,
+    /// players who list this player as an opponent, but whom this player doesn't list, in name
+    /// order
+    pub unlisted: Vec<String>,
+    /// listed opponents whom the player also plays in the matches, in name order
+    pub rematches: Vec<String>,
+}
+
+/// Check the listed opponents of every player in `players` against those of the other players
+/// and against the remaining `matches`.
+///
+/// Every match recorded in the players data should be listed for both of its players, so an
+/// opponent who lists a player that doesn't list them back points to a list that wasn't updated.
+/// The matches are the ones still to be counted, so a match against an opponent who is already
+/// listed is either a rematch or a match counted twice. Opponents and matches with players that
+/// are not in `players`, and byes, can't be checked and are skipped. Returns the players with
+/// either kind of disagreement, in name order.
+pub fn check_opponent_lists(
+    players: &HashMap<String, Player>,
+    matches: &[Pairing],
+) -> Vec<OpponentListMismatch> {
+    let mut unlisted: HashMap<&str, Vec<String>> = HashMap::new();
+    for player in players.values() {
+        for name in &player.opponents {
+            if let Some(opponent) = players.get(name) {
+                if !opponent.opponents.contains(&player.name) {
+                    unlisted
+                        .entry(&opponent.name)
+                        .or_default()
+                        .push(player.name.clone());
+                }
+            }
+        }
+    }
+    let mut rematches: HashMap<&str, Vec<String>> = HashMap::new();
+    for match_ in matches {
+        for name in match_.players() {
+            let player = match players.get(name) {
+                Some(player) => player,
+                None => continue,
+            };
+            for opponent in match_.players().filter(|&other| other != name) {
+                if player.opponents.contains(opponent) {
+                    rematches
+                        .entry(&player.name)
+                        .or_default()
+                        .push(opponent.clone());
+                }
+            }
+        }
+    }
+    let mut names = unlisted.keys().chain(rematches.keys()).collect::<Vec<_>>();
+    names.sort_unstable();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|&name| {
+            let sorted = |lists: &HashMap<&str, Vec<String>>| {
+                let mut list = lists.get(name).cloned().unwrap_or_default();
+                list.sort_unstable();
+                list.dedup();
+                list
+            };
+            OpponentListMismatch {
+                name: name.to_string(),
+                unlisted: sorted(&unlisted),
+                rematches: sorted(&rematches),
+            }
+        })
+        .collect()
+}
+
+/// Compute the number of possible outcomes of the matches without a result; see
+/// [`FieldMatch::outcome_count`].
+///
+/// Returns None if the number of outcomes does not fit in a usize.
+pub fn simulation_space(matches: &[FieldMatch], draws: bool) -> Option<usize> {
+    matches
+        .iter()
+        .filter(|match_| match_.result.is_none())
+        .try_fold(1usize, |space, match_| {
+            space.checked_mul(match_.outcome_count(draws))
+        })
+}
+
+/// Compute the outcome of each match in `outcomes` for an exhaustive simulation.
+///
+/// Digit `n` of `iteration` determines the outcome of the `n`th match without a result, in the
+/// order of [`FieldMatch::outcome`]. Each digit has as many values as its match has outcomes, so
+/// with only best-of-one matches the digits are in base 2, or in base 3 if `draws` is set. Matches
+/// with a result always have it as their outcome. Matches beyond the digits of the iteration index
+/// are always won by the first player without dropping a game.
+///
+/// Returns the weight of the simulation: the probability of these outcomes, scaled so that the
+/// weights of all simulations sum to their number. With only evenly matched best-of-one outcomes,
+/// every weight is 1.
+pub fn iteration_outcomes(
+    iteration: usize,
+    matches: &[FieldMatch],
+    draws: bool,
+    outcomes: &mut [Outcome],
+) -> f64 {
+    let mut digits = iteration;
+    let mut weight = 1.0;
+    for (match_, outcome) in matches.iter().zip(outcomes) {
+        if let Some(result) = match_.result {
+            *outcome = result;
+            continue;
+        }
+        let base = match_.outcome_count(draws);
+        *outcome = match_.outcome(digits % base);
+        digits /= base;
+        weight *= base as f64 * match_.probability(*outcome);
+    }
+    weight
+}
+
+/// Run `simulations` simulations split across `threads` threads.
+///
+/// `outcomes` fills in the match outcomes for each simulation index and returns the weight of the
+/// simulation, and `progress` is called with the index of each finished simulation. Each thread
+/// tallies its own placements, and the tallies are merged once all threads finish.
+pub fn run_simulations<F, P>(
+    simulations: usize,
+    threads: usize,
+    outcomes: F,
+    progress: P,
+    config: SimConfig,
+    matches: &[FieldMatch],
+    field: &[Player],
+) -> Tally
+where
+    F: Fn(usize, &mut [Outcome]) -> f64 + Sync,
+    P: Fn(usize) + Sync,
+{
+    run_in_chunks(simulations, threads, config, field, |range, tally| {
+        let mut scratch = Scratch::new(matches.len(), field);
+        for i in range {
+            let weight = outcomes(i, &mut scratch.outcomes);
+            simulate(config, matches, field, weight, &mut scratch, tally);
+            progress(i);
+        }
+    })
+}
+
+/// Split the simulation indices up to `simulations` into a contiguous chunk per thread, and run
+/// `worker` on each chunk with a tally of its own, on a rayon pool of `threads` threads.
+///
+/// The tallies are merged in chunk order once all chunks finish, so that the results don't depend
+/// on which thread ran which chunk.
+fn run_in_chunks<W>(
+    simulations: usize,
+    threads: usize,
+    config: SimConfig,
+    field: &[Player],
+    worker: W,
+) -> Tally
+where
+    W: Fn(std::ops::Range<usize>, &mut Tally) + Sync,
+{
+    let threads = threads.clamp(1, std::cmp::max(simulations, 1));
+    let chunk_size = simulations.div_ceil(threads);
+    let run_chunk = |thread: usize| {
+        let start = thread * chunk_size;
+        let end = std::cmp::min(start + chunk_size, simulations);
+        let mut tally = Tally::for_config(field, config);
+        worker(start..end, &mut tally);
+        tally
+    };
+    let tallies = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => pool.install(|| (0..threads).into_par_iter().map(run_chunk).collect()),
+        // Without a pool of our own, run on the global one, which has a thread per CPU.
+        Err(_) => (0..threads)
+            .into_par_iter()
+            .map(run_chunk)
+            .collect::<Vec<_>>(),
+    };
+    tallies
+        .into_iter()
+        .fold(Tally::for_config(field, config), Tally::merge)
+}
+
+/// Walks the outcomes of an exhaustive run in reflected Gray code order, so that consecutive
+/// simulations differ in the outcome of exactly one match.
+///
+/// The digits are the same as for [`iteration_outcomes`], but each digit runs forward while the
+/// number formed by the digits above it is even, and backward while it is odd. Every outcome is
+/// still visited exactly once over the whole simulation space, but a prefix of the order covers
+/// different outcomes than a prefix of [`iteration_outcomes`].
+struct GrayCode {
+    /// indices of the matches without a result, one per digit, least significant first
+    free: Vec<usize>,
+    /// number of outcomes of each digit's match
+    radices: Vec<usize>,
+    /// product of the radices of the digits below each digit, with one more for the whole space
+    place_values: Vec<usize>,
+    /// the plain digits of the current index
+    counter: Vec<usize>,
+    /// the Gray code digits of the current index, which select the outcomes
+    digits: Vec<usize>,
+    /// the current index
+    index: usize,
+}
+
+impl GrayCode {
+    /// start at `index`, which must be within the simulation space of `matches`
+    fn new(index: usize, matches: &[FieldMatch], draws: bool) -> GrayCode {
+        let free = (0..matches.len())
+            .filter(|&m| matches[m].result.is_none())
+            .collect::<Vec<_>>();
+        let radices = free
+            .iter()
+            .map(|&m| matches[m].outcome_count(draws))
+            .collect::<Vec<_>>();
+        let mut place_values = vec![1];
+        for &radix in &radices {
+            let last = place_values[place_values.len() - 1];
+            place_values.push(radix.saturating_mul(last));
+        }
+        let counter = (0..free.len())
+            .map(|k| index / place_values[k] % radices[k])
+            .collect::<Vec<_>>();
+        let mut code = GrayCode {
+            free,
+            radices,
+            place_values,
+            counter,
+            digits: Vec::new(),
+            index,
+        };
+        code.digits = (0..code.free.len()).map(|k| code.digit(k)).collect();
+        code
+    }
+
+    /// the Gray code digit `k` of the current index
+    fn digit(&self, k: usize) -> usize {
+        if (self.index / self.place_values[k + 1]) % 2 == 1 {
+            self.radices[k] - 1 - self.counter[k]
+        } else {
+            self.counter[k]
+        }
+    }
+
+    /// set the outcome of every match in `outcomes` for the current index
+    fn outcomes(&self, matches: &[FieldMatch], outcomes: &mut [Outcome]) {
+        for (match_, outcome) in matches.iter().zip(outcomes.iter_mut()) {
+            if let Some(result) = match_.result {
+                *outcome = result;
+            }
+        }
+        for (&m, &digit) in self.free.iter().zip(&self.digits) {
+            outcomes[m] = matches[m].outcome(digit);
+        }
+    }
+
+    /// move to the next index, and return the index of the match whose outcome changed with its
+    /// new digit
+    fn advance(&mut self) -> Option<(usize, usize)> {
+        self.index += 1;
+        let mut k = 0;
+        while k < self.counter.len() && self.counter[k] + 1 == self.radices[k] {
+            self.counter[k] = 0;
+            k += 1;
+        }
+        if k == self.counter.len() {
+            return None;
+        }
+        self.counter[k] += 1;
+        self.digits[k] = self.digit(k);
+        Some((self.free[k], self.digits[k]))
+    }
+}
+
+/// Compute the weight of the simulation with `outcomes`, as [`iteration_outcomes`] does.
+fn outcomes_weight(matches: &[FieldMatch], draws: bool, outcomes: &[Outcome]) -> f64 {
+    matches
+        .iter()
+        .zip(outcomes)
+        .filter(|(match_, _)| match_.result.is_none())
+        .fold(1.0, |weight, (match_, &outcome)| {
+            weight * match_.outcome_count(draws) as f64 * match_.probability(outcome)
+        })
+}
+
+/// Run the simulations at `range` of the Gray code order of an exhaustive run, tallying them in
+/// `tally`.
+///
+/// Rather than applying every match for every simulation, each step undoes the previous outcome
+/// of the one match that changed and applies its new outcome. The tally of the whole space is the
+/// same as that of [`iteration_outcomes`], up to the rounding of the weights.
+#[allow(clippy::too_many_arguments)]
+fn simulate_gray<P>(
+    range: std::ops::Range<usize>,
+    config: SimConfig,
+    matches: &[FieldMatch],
+    field: &[Player],
+    scratch: &mut Scratch,
+    tally: &mut Tally,
+    progress: &P,
+) where
+    P: Fn(usize),
+{
+    if range.is_empty() {
+        return;
+    }
+    let mut code = GrayCode::new(range.start, matches, config.draws);
+    code.outcomes(matches, &mut scratch.outcomes);
+    for (player, base) in scratch.players.iter_mut().zip(field) {
+        player.reset(base);
+    }
+    for (match_, &outcome) in matches.iter().zip(&scratch.outcomes) {
+        apply_outcome(config, match_, outcome, field, &mut scratch.players, false);
+    }
+    for i in range.clone() {
+        let weight = outcomes_weight(matches, config.draws, &scratch.outcomes);
+        rank_applied(config, field, scratch);
+        tally.add_ranking(&scratch.ranking, &scratch.ties, weight);
+        progress(i);
+        if i + 1 == range.end {
+            break;
+        }
+        if let Some((m, digit)) = code.advance() {
+            let players = &mut scratch.players;
+            apply_outcome(
+                config,
+                &matches[m],
+                scratch.outcomes[m],
+                field,
+                players,
+                true,
+            );
+            scratch.outcomes[m] = matches[m].outcome(digit);
+            apply_outcome(
+                config,
+                &matches[m],
+                scratch.outcomes[m],
+                field,
+                players,
+                false,
+            );
+        }
+    }
+}
+
+/// Record the placement counts and expected ranks in `tally` on each player, mapping the indices of
+/// `field` back to player names.
+pub fn record_placements(players: &mut HashMap<String, Player>, field: &[Player], tally: &Tally) {
+    for (id, base) in field.iter().enumerate() {
+        if let Some(player) = players.get_mut(&base.name) {
+            player.expected_rank = tally.expected_rank(id);
+            for (rank, count) in tally.placements(id) {
+                player.add_placements(rank, count);
+            }
+        }
+    }
+}
+
+/// Buffers reused across the simulations run on a single thread
+pub struct Scratch {
+    /// outcome of each match
+    pub outcomes: Vec<Outcome>,
+    /// state of each player in the simulated field
+    players: Vec<Player>,
+    /// indices of the players, in ranked order
+    ranking: Vec<usize>,
+    /// whether each player of `ranking` is tied with the next one after every tiebreaker, if
+    /// ties are split
+    ties: Vec<bool>,
+    /// scores of the opponents of a player, for computing Buchholz scores
+    opp_scores: Vec<f64>,
+}
+
+impl Scratch {
+    /// create buffers for simulating `match_count` matches between the players of `field`
+    pub fn new(match_count: usize, field: &[Player]) -> Scratch {
+        Scratch {
+            outcomes: vec![Outcome::Player1Wins(0); match_count],
+            players: field.to_vec(),
+            ranking: (0..field.len()).collect(),
+            ties: Vec::new(),
+            opp_scores: Vec::new(),
+        }
+    }
+}
+
+/// Simulate the match results in `scratch.outcomes` and record the top placements in `tally`, with
+/// the given weight.
+///
+/// The simulation starts from the records in `field` and runs on the players in `scratch`.
+pub fn simulate(
+    config: SimConfig,
+    matches: &[FieldMatch],
+    field: &[Player],
+    weight: f64,
+    scratch: &mut Scratch,
+    tally: &mut Tally,
+) {
+    rank_simulation(config, matches, field, scratch);
+    tally.add_ranking(&scratch.ranking, &scratch.ties, weight);
+}
+
+/// Apply the match results in `scratch.outcomes` to the records in `field`, and rank the players
+/// into `scratch.ranking`.
+fn rank_simulation(
+    config: SimConfig,
+    matches: &[FieldMatch],
+    field: &[Player],
+    scratch: &mut Scratch,
+) {
+    let players = &mut scratch.players;
+    for (player, base) in players.iter_mut().zip(field) {
+        player.reset(base);
+    }
+    for (match_, &outcome) in matches.iter().zip(&scratch.outcomes) {
+        apply_outcome(config, match_, outcome, field, players, false);
+    }
+    rank_applied(config, field, scratch);
+}
+
+/// Apply `outcome` of `match_` to the records of `players`, or undo it if `undo` is set.
+///
+/// Undoing an outcome after applying it restores the records as they were.
+fn apply_outcome(
+    config: SimConfig,
+    match_: &FieldMatch,
+    outcome: Outcome,
+    field: &[Player],
+    players: &mut [Player],
+    undo: bool,
+) {
+    let (games1, games2) = match_.games(outcome);
+    for (player, won, lost) in [
+        (match_.player1, games1, games2),
+        (match_.player2, games2, games1),
+    ] {
+        if let Some(player) = player {
+            if undo {
+                players[player].remove_games(won, lost);
+            } else {
+                players[player].add_games(won, lost);
+            }
+            if config.trust_csv_opp {
+                for &opponent in &field[player].opponent_ids {
+                    if undo {
+                        players[opponent].remove_opponent_games(won, lost);
+                    } else {
+                        players[opponent].add_opponent_games(won, lost);
+                    }
+                }
+            }
+        }
+    }
+    let winner_pos = match match_.winner(outcome) {
+        Some(pos) => pos,
+        None => {
+            for player in match_.player1.iter().chain(&match_.player2) {
+                if undo {
+                    players[*player].remove_draw();
+                } else {
+                    players[*player].add_draw();
+                }
+            }
+            return;
+        }
+    };
+    // Every other player of the match loses it: the opponent, or the rest of a pod.
+    let losers = (0..match_.size)
+        .filter(|&pos| pos != winner_pos)
+        .filter_map(|pos| match_.player(pos));
+
+    if let Some(winner) = match_.player(winner_pos) {
+        if undo {
+            players[winner].remove_win(losers.clone());
+        } else {
+            players[winner].add_win(losers.clone());
+        }
+        if match_.bye {
+            if undo {
+                players[winner].remove_bye();
+            } else {
+                players[winner].add_bye();
+            }
+        }
+        if config.trust_csv_opp {
+            for &opponent in &field[winner].opponent_ids {
+                if undo {
+                    players[opponent].remove_opponent_win();
+                } else {
+                    players[opponent].add_opponent_win();
+                }
+            }
+        }
+    }
+    for loser in losers {
+        if undo {
+            players[loser].remove_loss();
+        } else {
+            players[loser].add_loss();
+        }
+        if config.trust_csv_opp {
+            for &opponent in &field[loser].opponent_ids {
+                if undo {
+                    players[opponent].remove_opponent_loss();
+                } else {
+                    players[opponent].add_opponent_loss();
+                }
+            }
+        }
+    }
+}
+
+/// Rank the players in `scratch`, whose records already have the match results applied, into
+/// `scratch.ranking`.
+fn rank_applied(config: SimConfig, field: &[Player], scratch: &mut Scratch) {
+    let players = &mut scratch.players;
+    compute_head_to_head(players, config);
+    // The opponent winrates feed the opponent-of-opponent winrate whatever the tiebreaker.
+    compute_opponent_winrates(players, field, config);
+    compute_opp_opp_winrates(players);
+    let tiebreakers = config.tiebreakers;
+    if tiebreakers.contains(Tiebreaker::OpponentGameWinrate) {
+        compute_opponent_game_winrates(players, config);
+    }
+    if tiebreakers.contains(Tiebreaker::Buchholz)
+        || tiebreakers.contains(Tiebreaker::MedianBuchholz)
+    {
+        compute_buchholz(players, config, &mut scratch.opp_scores);
+    }
+    let ranking = &mut scratch.ranking;
+    // Reverse the sort to get highest score first. Players never compare equal, so an unstable
+    // sort, which needs no extra memory, gives the same ranking as a stable one.
+    ranking.sort_unstable_by(|&p1, &p2| rank_players(&players[p1], &players[p2], config).reverse());
+    if config.split_ties {
+        scratch.ties.clear();
+        scratch.ties.extend(ranking.windows(2).map(|pair| {
+            let (p1, p2) = (&players[pair[0]], &players[pair[1]]);
+            cmp_score(p1, p2, config) == Ordering::Equal
+                && tiebreak(p1, p2, config.tiebreakers) == Ordering::Equal
+        }));
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Final record of a player in a single simulation
+pub struct Standing {
+    /// name of the player
+    pub name: String,
+    /// match points of the player
+    pub points: f64,
+    /// number of wins by the player
+    pub wins: u32,
+    /// number of losses by the player
+    pub losses: u32,
+    /// number of draws by the player
+    pub draws: u32,
+}
+
+#[derive(Clone, Debug)]
+/// Final standings of a single simulation
+pub struct Standings {
+    /// index of the simulation in its run
+    pub simulation: usize,
+    /// weight of the simulation; see [`iteration_outcomes`]
+    pub weight: f64,
+    /// the players of the simulated field, in ranked order
+    pub players: Vec<Standing>,
+}
+
+impl Standings {
+    /// copy the standings of the simulation last ranked in `scratch`
+    fn new(simulation: usize, weight: f64, scratch: &Scratch, config: SimConfig) -> Standings {
+        let players = scratch
+            .ranking
+            .iter()
+            .map(|&id| {
+                let player = &scratch.players[id];
+                Standing {
+                    name: player.name.clone(),
+                    points: score(player, config),
+                    wins: player.wins,
+                    losses: player.losses,
+                    draws: player.draws,
+                }
+            })
+            .collect();
+        Standings {
+            simulation,
+            weight,
+            players,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A match as it went in a single simulation
+pub struct ReplayedMatch {
+    /// first player of the match, as in its [`Pairing`]
+    pub player1: String,
+    /// second player of the match, as in its [`Pairing`]
+    pub player2: String,
+    /// further players of a multiplayer pod, as in its [`Pairing`]
+    pub others: Vec<String>,
+    /// outcome of the match
+    pub outcome: Outcome,
+    /// games won by player1 and by player2
+    pub games: (u32, u32),
+    /// whether the outcome is the result of the match data rather than simulated
+    pub played: bool,
+}
+
+#[derive(Clone, Debug)]
+/// A single simulation in detail: how each match went, and the standings that came of it
+pub struct Replay {
+    /// every match, in the order of the digits of [`iteration_outcomes`]
+    pub matches: Vec<ReplayedMatch>,
+    /// the final standings
+    pub standings: Standings,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Whether a player controls their own destiny: which outcomes of their own matches guarantee
+/// them a top-rank finish, whatever the outcomes of the other matches
+pub struct Destiny {
+    /// number of remaining matches played by the player
+    pub matches: usize,
+    /// number of possible outcomes of the player's matches that guarantee a top-rank finish
+    pub favorable: usize,
+    /// number of possible outcomes of the player's matches that don't
+    pub unfavorable: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// How a player's chance of a top-rank finish depends on the outcome of one remaining match
+pub struct MatchImpact {
+    /// first player of the match, as in its [`Pairing`]
+    pub player1: String,
+    /// second player of the match, as in its [`Pairing`]
+    pub player2: String,
+    /// chance of a top-rank finish if player1 wins the match, or None if they can't
+    pub player1_wins: Option<f64>,
+    /// chance of a top-rank finish if player2 wins the match, or None if they can't
+    pub player2_wins: Option<f64>,
+    /// chance of a top-rank finish if the match is drawn, or None if it can't be
+    pub draw: Option<f64>,
+    /// the further players of a multiplayer pod, each with the chance of a top-rank finish if
+    /// they win it
+    pub others: Vec<(String, Option<f64>)>,
+}
+
+impl MatchImpact {
+    /// the difference between the highest and the lowest chance of a top-rank finish over the
+    /// possible outcomes of the match
+    pub fn impact(&self) -> f64 {
+        let chances = [self.player1_wins, self.player2_wins, self.draw];
+        let others = self.others.iter().map(|(_, chance)| chance);
+        let (low, high) = chances
+            .iter()
+            .chain(others)
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &p| {
+                (low.min(p), high.max(p))
+            });
+        if low <= high {
+            high - low
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Set the outcomes of the matches at `indices` in `outcomes` from the digits of `index`, like
+/// [`iteration_outcomes`] does for all matches.
+///
+/// Returns false if any of these outcomes is impossible because its probability is 0.
+fn set_outcomes(
+    index: usize,
+    indices: &[usize],
+    matches: &[FieldMatch],
+    draws: bool,
+    outcomes: &mut [Outcome],
+) -> bool {
+    let mut digits = index;
+    let mut possible = true;
+    for &m in indices {
+        let base = matches[m].outcome_count(draws);
+        outcomes[m] = matches[m].outcome(digits % base);
+        digits /= base;
+        possible &= matches[m].probability(outcomes[m]) > 0.0;
+    }
+    possible
+}
+
+/// Find the outcomes of the matches of player `id` that guarantee them a top-rank finish.
+///
+/// The matches without a result are partitioned into those the player takes part in, whose
+/// outcomes they control, and the rest. Each possible outcome of the player's matches is
+/// favorable if the player finishes in the top ranks for every possible outcome of the rest.
+/// Outcomes that are impossible because their probability is 0 are not counted.
+///
+/// This enumerates every outcome of every match, so the number of outcomes must fit in a usize;
+/// see [`simulation_space`].
+fn destiny(config: SimConfig, matches: &[FieldMatch], field: &[Player], id: usize) -> Destiny {
+    let (own, rest): (Vec<usize>, Vec<usize>) = (0..matches.len())
+        .filter(|&m| matches[m].result.is_none())
+        .partition(|&m| matches[m].involves(id));
+    let space = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|&m| matches[m].outcome_count(config.draws))
+            .product::<usize>()
+    };
+    let mut scratch = Scratch::new(matches.len(), field);
+    for (match_, outcome) in matches.iter().zip(&mut scratch.outcomes) {
+        if let Some(result) = match_.result {
+            *outcome = result;
+        }
+    }
+    let mut destiny = Destiny {
+        matches: own.len(),
+        favorable: 0,
+        unfavorable: 0,
+    };
+    for controlled in 0..space(&own) {
+        if !set_outcomes(
+            controlled,
+            &own,
+            matches,
+            config.draws,
+            &mut scratch.outcomes,
+        ) {
+            continue;
+        }
+        let guaranteed = (0..space(&rest)).all(|other| {
+            if !set_outcomes(other, &rest, matches, config.draws, &mut scratch.outcomes) {
+                return true;
+            }
+            rank_simulation(config, matches, field, &mut scratch);
+            scratch.ranking[..config.top_ranks.min(field.len())].contains(&id)
+        });
+        if guaranteed {
+            destiny.favorable += 1;
+        } else {
+            destiny.unfavorable += 1;
+        }
+    }
+    destiny
+}
+
+/// Find how the chance of player `id` finishing in the top ranks depends on the outcome of each
+/// match without a result; see [`MatchImpact`].
+///
+/// Every outcome of the matches is simulated once, weighted by its probability as in
+/// [`iteration_outcomes`]. For each match, the simulations are split by who won it, or whether it
+/// was drawn, and the chance of a top-rank finish is the weighted share of each part in which the
+/// player placed. `pairings` are the matches as loaded, which name their players. Returns the
+/// impacts in the order of the matches.
+///
+/// The number of outcomes must fit in a usize; see [`simulation_space`].
+fn match_impact(
+    config: SimConfig,
+    matches: &[FieldMatch],
+    pairings: &[Pairing],
+    field: &[Player],
+    id: usize,
+) -> Vec<MatchImpact> {
+    let undecided = (0..matches.len())
+        .filter(|&m| matches[m].result.is_none())
+        .collect::<Vec<_>>();
+    let space = undecided
+        .iter()
+        .map(|&m| matches[m].outcome_count(config.draws))
+        .product::<usize>();
+    // Weights of the simulations with each match won by player1, won by player2, drawn, or won by
+    // each further player of a pod, and of those of them in which the player placed.
+    let mut weights = vec![[0.0; MAX_POD_SIZE + 1]; matches.len()];
+    let mut placed = vec![[0.0; MAX_POD_SIZE + 1]; matches.len()];
+    let mut scratch = Scratch::new(matches.len(), field);
+    for i in 0..space {
+        let weight = iteration_outcomes(i, matches, config.draws, &mut scratch.outcomes);
+        if weight <= 0.0 {
+            continue;
+        }
+        rank_simulation(config, matches, field, &mut scratch);
+        let top = scratch.ranking[..config.top_ranks.min(field.len())].contains(&id);
+        for &m in &undecided {
+            let part = match scratch.outcomes[m] {
+                Outcome::Player1Wins(_) => 0,
+                Outcome::Player2Wins(_) => 1,
+                Outcome::Draw => 2,
+                Outcome::OtherWins(other) => 3 + other,
+            };
+            weights[m][part] += weight;
+            if top {
+                placed[m][part] += weight;
+            }
+        }
+    }
+    undecided
+        .into_iter()
+        .map(|m| {
+            let chance =
+                |part: usize| (weights[m][part] > 0.0).then(|| placed[m][part] / weights[m][part]);
+            MatchImpact {
+                player1: pairings[m].player1.clone(),
+                player2: pairings[m].player2.clone(),
+                player1_wins: chance(0),
+                player2_wins: chance(1),
+                draw: chance(2),
+                others: pairings[m]
+                    .others
+                    .iter()
+                    .enumerate()
+                    .map(|(other, name)| (name.clone(), chance(3 + other)))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Builder for a [`Simulator`]
+///
+/// By default, simulations count the top 8 ranks, break ties by [`DEFAULT_TIEBREAKERS`]
+/// (head-to-head results, then opponent winrate, then opponent-of-opponent winrate), and run on
+/// one thread per CPU.
+pub struct SimulatorBuilder {
+    players: HashMap<String, Player>,
+    matches: Vec<Pairing>,
+    config: SimConfig,
+    threads: Option<usize>,
+    prior_momentum: f64,
+    progress: Option<Box<dyn Fn(usize) + Sync>>,
+}
+
+impl SimulatorBuilder {
+    /// start building a simulator for the remaining `matches` between `players`
+    pub fn new(players: HashMap<String, Player>, matches: Vec<Pairing>) -> SimulatorBuilder {
+        SimulatorBuilder {
+            players,
+            matches,
+            config: SimConfig {
+                top_ranks: 8,
+                rank_by: RankBy::Points,
+                tiebreakers: Tiebreakers::default(),
+                trust_csv_opp: false,
+                winrate_floor: DEFAULT_WINRATE_FLOOR,
+                pooled_opp_winrate: false,
+                pairwise: false,
+                distinct_standings: false,
+                split_ties: false,
+                draws: false,
+                points: PointValues::default(),
+            },
+            threads: None,
+            prior_momentum: 0.0,
+            progress: None,
+        }
+    }
+
+    /// set the number of top ranks to count in each simulation
+    pub fn top_ranks(mut self, top_ranks: usize) -> SimulatorBuilder {
+        self.config.top_ranks = top_ranks;
+        self
+    }
+
+    /// set what players are ranked by, before tiebreakers
+    pub fn rank_by(mut self, rank_by: RankBy) -> SimulatorBuilder {
+        self.config.rank_by = rank_by;
+        self
+    }
+
+    /// break ties with `tiebreaker` alone; see [`SimulatorBuilder::tiebreakers`]
+    pub fn tiebreaker(mut self, tiebreaker: Tiebreaker) -> SimulatorBuilder {
+        self.config.tiebreakers = Tiebreakers::from(tiebreaker);
+        self
+    }
+
+    /// set the chain of tiebreakers to use, in order
+    pub fn tiebreakers(mut self, tiebreakers: Tiebreakers) -> SimulatorBuilder {
+        self.config.tiebreakers = tiebreakers;
+        self
+    }
+
+    /// keep the opponent records from the player data, rather than recomputing them
+    pub fn trust_csv_opp(mut self, trust_csv_opp: bool) -> SimulatorBuilder {
+        self.config.trust_csv_opp = trust_csv_opp;
+        self
+    }
+
+    /// set the lowest winrate each opponent counts with in opponent winrates
+    pub fn winrate_floor(mut self, winrate_floor: f64) -> SimulatorBuilder {
+        self.config.winrate_floor = winrate_floor;
+        self
+    }
+
+    /// pool the records of all opponents for opponent winrates, rather than averaging the winrate
+    /// of each opponent
+    pub fn pooled_opp_winrate(mut self, pooled_opp_winrate: bool) -> SimulatorBuilder {
+        self.config.pooled_opp_winrate = pooled_opp_winrate;
+        self
+    }
+
+    /// allow matches to be drawn
+    pub fn draws(mut self, draws: bool) -> SimulatorBuilder {
+        self.config.draws = draws;
+        self
+    }
+
+    /// set the match points for each match result, which players are ranked by
+    pub fn points(mut self, points: PointValues) -> SimulatorBuilder {
+        self.config.points = points;
+        self
+    }
+
+    /// count how often each player finishes above each other player; see
+    /// [`Results::finishes_above`]
+    pub fn pairwise(mut self, pairwise: bool) -> SimulatorBuilder {
+        self.config.pairwise = pairwise;
+        self
+    }
+
+    /// count how often each order of the players in the top ranks comes up, for
+    /// [`Results::distinct_standings`]; see [`Tally::with_standings`]
+    pub fn distinct_standings(mut self, distinct_standings: bool) -> SimulatorBuilder {
+        self.config.distinct_standings = distinct_standings;
+        self
+    }
+
+    /// let players still tied after every tiebreaker share the placements of the ranks they
+    /// cover equally, rather than placing them in name order
+    ///
+    /// Two players tied for the last of the top ranks each get half a placement at that rank.
+    /// Expected ranks, pairwise counts, and standings still place tied players in name order.
+    pub fn split_ties(mut self, split_ties: bool) -> SimulatorBuilder {
+        self.config.split_ties = split_ties;
+        self
+    }
+
+    /// favor the player with more wins before the round in matches without a win probability or
+    /// ratings, by `prior_momentum` per win of difference; see [`resolve_matches`]
+    pub fn prior_momentum(mut self, prior_momentum: f64) -> SimulatorBuilder {
+        self.prior_momentum = prior_momentum;
+        self
+    }
+
+    /// set the number of threads to run simulations on
+    pub fn threads(mut self, threads: usize) -> SimulatorBuilder {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// call `progress` with the index of each finished simulation
+    pub fn progress(mut self, progress: impl Fn(usize) + Sync + 'static) -> SimulatorBuilder {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// index the players and resolve the matches to build the simulator
+    pub fn build(self) -> Simulator {
+        let field = index_players(&self.players);
+        let matches = resolve_matches(
+            &self.matches,
+            &field,
+            self.config.draws,
+            self.prior_momentum,
+        );
+        let threads = self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        });
+        Simulator {
+            players: self.players,
+            field,
+            matches,
+            pairings: self.matches,
+            config: self.config,
+            threads,
+            progress: self.progress,
+        }
+    }
+}
+
+/// Simulates the remaining matches of an event and tallies the final placements
+pub struct Simulator {
+    /// players as loaded, keyed by name
+    players: HashMap<String, Player>,
+    /// players of the simulated field, ordered by index
+    field: Vec<Player>,
+    /// matches to simulate
+    matches: Vec<FieldMatch>,
+    /// matches as loaded, in the same order
+    pairings: Vec<Pairing>,
+    config: SimConfig,
+    threads: usize,
+    progress: Option<Box<dyn Fn(usize) + Sync>>,
+}
+
+impl Simulator {
+    /// start building a simulator for the remaining `matches` between `players`
+    pub fn builder(players: HashMap<String, Player>, matches: Vec<Pairing>) -> SimulatorBuilder {
+        SimulatorBuilder::new(players, matches)
+    }
+
+    /// the number of matches to simulate, excluding those that already have a result
+    pub fn match_count(&self) -> usize {
+        self.matches
+            .iter()
+            .filter(|match_| match_.result.is_none())
+            .count()
+    }
+
+    /// the number of possible outcomes of the matches, or None if it does not fit in a usize
+    pub fn simulation_space(&self) -> Option<usize> {
+        simulation_space(&self.matches, self.config.draws)
+    }
+
+    /// Simulate every possible outcome of the matches.
+    ///
+    /// The outcomes are visited in Gray code order, so that each simulation only needs to update
+    /// the one match that changed since the last one. This gives the same results as
+    /// [`Simulator::run_first`] over the whole simulation space, but faster; weighted placements
+    /// may differ by rounding, as they are summed in a different order.
+    ///
+    /// Returns None if there are too many outcomes to enumerate; see
+    /// [`Simulator::simulation_space`].
+    pub fn run_exhaustive(&self) -> Option<Results> {
+        let space = self.simulation_space()?;
+        let progress = |i| {
+            if let Some(progress) = &self.progress {
+                progress(i);
+            }
+        };
+        let (config, matches, field) = (self.config, &self.matches, &self.field);
+        let tally = run_in_chunks(space, self.threads, config, field, |range, tally| {
+            let mut scratch = Scratch::new(matches.len(), field);
+            simulate_gray(
+                range,
+                config,
+                matches,
+                field,
+                &mut scratch,
+                tally,
+                &progress,
+            );
+        });
+        Some(self.results(&tally, true))
+    }
+
+    /// Simulate the first `simulations` outcomes of an exhaustive run.
+    ///
+    /// This is useful for checking a large simulation before running all of it.
+    pub fn run_first(&self, simulations: usize) -> Results {
+        let matches = &self.matches;
+        let draws = self.config.draws;
+        let exhaustive = self.simulation_space() == Some(simulations);
+        self.run(simulations, exhaustive, |i, outcomes| {
+            iteration_outcomes(i, matches, draws, outcomes)
+        })
+    }
+
+    /// Find whether the player named `name` controls their own destiny; see [`Destiny`].
+    ///
+    /// Returns None if there is no such player, or if there are too many outcomes to enumerate.
+    pub fn destiny(&self, name: &str) -> Option<Destiny> {
+        self.simulation_space()?;
+        let id = self.field.iter().position(|player| player.name == name)?;
+        Some(destiny(self.config, &self.matches, &self.field, id))
+    }
+
+    /// Find how the chance of the player named `name` finishing in the top ranks depends on the
+    /// outcome of each remaining match; see [`MatchImpact`]. The matches are sorted by impact,
+    /// highest first.
+    ///
+    /// Returns None if there is no such player, or if there are too many outcomes to enumerate.
+    pub fn match_impact(&self, name: &str) -> Option<Vec<MatchImpact>> {
+        self.simulation_space()?;
+        let id = self.field.iter().position(|player| player.name == name)?;
+        let mut impacts = match_impact(self.config, &self.matches, &self.pairings, &self.field, id);
+        impacts.sort_by(|a, b| b.impact().total_cmp(&a.impact()));
+        Some(impacts)
+    }
+
+    /// Rank the players as they stand before the remaining matches, with the results of the
+    /// matches that have one and none of the others, as simulation 0 with a weight of 1.
+    pub fn baseline(&self) -> Standings {
+        let mut scratch = Scratch::new(self.matches.len(), &self.field);
+        for match_ in &self.matches {
+            if let Some(result) = match_.result {
+                apply_outcome(
+                    self.config,
+                    match_,
+                    result,
+                    &self.field,
+                    &mut scratch.players,
+                    false,
+                );
+            }
+        }
+        rank_applied(self.config, &self.field, &mut scratch);
+        Standings::new(0, 1.0, &scratch, self.config)
+    }
+
+    /// Replay simulation `simulation` of an exhaustive run, as [`Simulator::run_first`] runs it;
+    /// see [`Replay`].
+    pub fn replay_first(&self, simulation: usize) -> Replay {
+        let weight = |outcomes: &mut [Outcome]| {
+            iteration_outcomes(simulation, &self.matches, self.config.draws, outcomes)
+        };
+        self.replay(simulation, weight)
+    }
+
+    /// Replay sample `sample` of a sampled run with the given seed, as
+    /// [`Simulator::run_sampled`] draws it; see [`Replay`].
+    pub fn replay_sampled(&self, sample: usize, seed: u64) -> Replay {
+        self.replay(sample, |outcomes: &mut [Outcome]| {
+            sample_outcomes(seed, sample, &self.matches, outcomes)
+        })
+    }
+
+    fn replay(&self, simulation: usize, outcomes: impl FnOnce(&mut [Outcome]) -> f64) -> Replay {
+        let mut scratch = Scratch::new(self.matches.len(), &self.field);
+        let weight = outcomes(&mut scratch.outcomes);
+        rank_simulation(self.config, &self.matches, &self.field, &mut scratch);
+        let matches = self
+            .matches
+            .iter()
+            .zip(&self.pairings)
+            .zip(&scratch.outcomes)
+            .map(|((match_, pairing), &outcome)| ReplayedMatch {
+                player1: pairing.player1.clone(),
+                player2: pairing.player2.clone(),
+                others: pairing.others.clone(),
+                outcome,
+                games: match_.games(outcome),
+                played: match_.result.is_some(),
+            })
+            .collect();
+        Replay {
+            matches,
+            standings: Standings::new(simulation, weight, &scratch, self.config),
+        }
+    }
+
+    /// Simulate `samples` random outcomes of the matches, drawn with the given seed.
+    pub fn run_sampled(&self, samples: usize, seed: u64) -> Results {
+        let matches = &self.matches;
+        self.run(samples, false, |i, outcomes| {
+            sample_outcomes(seed, i, matches, outcomes)
+        })
+    }
+
+    /// Simulate random outcomes of the matches, drawn with the given seed, in batches of
+    /// `interval` until `done` returns true for the results so far, or `max_samples` have run.
+    ///
+    /// The results are the same as those of [`Simulator::run_sampled`] with the number of samples
+    /// that were run, which is their total weight.
+    pub fn run_sampled_until(
+        &self,
+        max_samples: usize,
+        seed: u64,
+        interval: usize,
+        mut done: impl FnMut(&Results) -> bool,
+    ) -> Results {
+        let matches = &self.matches;
+        let mut tally = Tally::for_config(&self.field, self.config);
+        let mut next: usize = 0;
+        loop {
+            let start = next;
+            let end = std::cmp::min(start.saturating_add(interval.max(1)), max_samples);
+            let batch = self.tally(
+                end - start,
+                |i, outcomes: &mut [Outcome]| sample_outcomes(seed, start + i, matches, outcomes),
+                start,
+            );
+            tally = tally.merge(batch);
+            next = end;
+            let results = self.results(&tally, false);
+            if next >= max_samples || done(&results) {
+                return results;
+            }
+        }
+    }
+
+    /// Start a checkpoint for a run of the first `simulations` outcomes of an exhaustive run.
+    pub fn checkpoint_first(&self, simulations: usize) -> Checkpoint {
+        self.new_checkpoint(simulations, None)
+    }
+
+    /// Start a checkpoint for a run of `samples` random outcomes, drawn with the given seed.
+    pub fn checkpoint_sampled(&self, samples: usize, seed: u64) -> Checkpoint {
+        self.new_checkpoint(samples, Some(seed))
+    }
+
+    fn new_checkpoint(&self, simulations: usize, seed: Option<u64>) -> Checkpoint {
+        Checkpoint {
+            simulations,
+            next: 0,
+            seed,
+            fingerprint: Fingerprint::new(&self.field, &self.matches).0,
+            tally: Tally::for_config(&self.field, self.config),
+        }
+    }
+
+    /// Run the rest of the simulations of `checkpoint`, as [`Simulator::run_first`] or
+    /// [`Simulator::run_sampled`] would, depending on whether the checkpoint has a seed.
+    ///
+    /// Simulations run in batches of `interval`, and `save` is called with the updated checkpoint
+    /// after each batch. Returns an error if the checkpoint is for different players or matches, or
+    /// a different number of top ranks.
+    pub fn resume(
+        &self,
+        checkpoint: Checkpoint,
+        interval: usize,
+        save: impl FnMut(&Checkpoint),
+    ) -> Result<Results, String> {
+        self.resume_until(checkpoint, interval, save, |_| false)
+    }
+
+    /// Run the rest of the simulations of `checkpoint` as [`Simulator::resume`] does, but stop
+    /// early once `done` returns true for the checkpoint after a batch that leaves simulations to
+    /// run.
+    ///
+    /// The results of a run that stops early are those of the simulations run so far, and don't
+    /// decide which players are clinched or eliminated, even if the run is of every outcome.
+    pub fn resume_until(
+        &self,
+        mut checkpoint: Checkpoint,
+        interval: usize,
+        mut save: impl FnMut(&Checkpoint),
+        mut done: impl FnMut(&Checkpoint) -> bool,
+    ) -> Result<Results, String> {
+        let tally = &checkpoint.tally;
+        if tally.rank_means.len() != self.field.len() || tally.top_ranks != self.config.top_ranks {
+            return Err(format!(
+                "checkpoint is for {} players and {} top ranks, but the simulation has {} players \
+                 and {} top ranks",
+                tally.rank_means.len(),
+                tally.top_ranks,
+                self.field.len(),
+                self.config.top_ranks
+            ));
+        }
+        if checkpoint.fingerprint != Fingerprint::new(&self.field, &self.matches).0 {
+            return Err(String::from(
+                "checkpoint is for different players or matches than the simulation",
+            ));
+        }
+        if self.config.pairwise && tally.above.is_empty() {
+            return Err(String::from(
+                "checkpoint has no pairwise counts, which checkpoints don't save",
+            ));
+        }
+        if self.config.distinct_standings && tally.standings.is_none() {
+            return Err(String::from(
+                "checkpoint has no counts of standings, which checkpoints don't save",
+            ));
+        }
+        let matches = &self.matches;
+        let draws = self.config.draws;
+        let seed = checkpoint.seed;
+        let outcomes = |i, outcomes: &mut [Outcome]| match seed {
+            Some(seed) => sample_outcomes(seed, i, matches, outcomes),
+            None => iteration_outcomes(i, matches, draws, outcomes),
+        };
+        while checkpoint.next < checkpoint.simulations {
+            let start = checkpoint.next;
+            let end = std::cmp::min(
+                start.saturating_add(interval.max(1)),
+                checkpoint.simulations,
+            );
+            let batch = self.tally(
+                end - start,
+                |i, scratch: &mut [Outcome]| outcomes(start + i, scratch),
+                start,
+            );
+            checkpoint.tally = checkpoint.tally.merge(batch);
+            checkpoint.next = end;
+            save(&checkpoint);
+            if checkpoint.next < checkpoint.simulations && done(&checkpoint) {
+                break;
+            }
+        }
+        let exhaustive = seed.is_none()
+            && checkpoint.next == checkpoint.simulations
+            && self.simulation_space() == Some(checkpoint.simulations);
+        Ok(self.results(&checkpoint.tally, exhaustive))
+    }
+
+    /// Iterate over the standings of the first `simulations` outcomes of an exhaustive run.
+    ///
+    /// Simulations run one at a time on the calling thread as the iterator advances, so only the
+    /// current standings are kept in memory.
+    pub fn standings_first(&self, simulations: usize) -> impl Iterator<Item = Standings> + '_ {
+        let matches = &self.matches;
+        let draws = self.config.draws;
+        self.standings(simulations, move |i, outcomes| {
+            iteration_outcomes(i, matches, draws, outcomes)
+        })
+    }
+
+    /// Iterate over the standings of `samples` random outcomes of the matches, drawn with the
+    /// given seed, as [`Simulator::run_sampled`] does.
+    pub fn standings_sampled(
+        &self,
+        samples: usize,
+        seed: u64,
+    ) -> impl Iterator<Item = Standings> + '_ {
+        let matches = &self.matches;
+        self.standings(samples, move |i, outcomes| {
+            sample_outcomes(seed, i, matches, outcomes)
+        })
+    }
+
+    fn standings<'a, F>(
+        &'a self,
+        simulations: usize,
+        outcomes: F,
+    ) -> impl Iterator<Item = Standings> + 'a
+    where
+        F: Fn(usize, &mut [Outcome]) -> f64 + 'a,
+    {
+        let mut scratch = Scratch::new(self.matches.len(), &self.field);
+        (0..simulations).map(move |i| {
+            let weight = outcomes(i, &mut scratch.outcomes);
+            rank_simulation(self.config, &self.matches, &self.field, &mut scratch);
+            Standings::new(i, weight, &scratch, self.config)
+        })
+    }
+
+    fn run<F>(&self, simulations: usize, exhaustive: bool, outcomes: F) -> Results
+    where
+        F: Fn(usize, &mut [Outcome]) -> f64 + Sync,
+    {
+        let tally = self.tally(simulations, outcomes, 0);
+        self.results(&tally, exhaustive)
+    }
+
+    /// Run `simulations` simulations on all threads, reporting progress with indices starting at
+    /// `first`.
+    fn tally<F>(&self, simulations: usize, outcomes: F, first: usize) -> Tally
+    where
+        F: Fn(usize, &mut [Outcome]) -> f64 + Sync,
+    {
+        let progress = |i| {
+            if let Some(progress) = &self.progress {
+                progress(first + i);
+            }
+        };
+        run_simulations(
+            simulations,
+            self.threads,
+            outcomes,
+            progress,
+            self.config,
+            &self.matches,
+            &self.field,
+        )
+    }
+
+    fn results(&self, tally: &Tally, exhaustive: bool) -> Results {
+        let mut players = self.players.clone();
+        record_placements(&mut players, &self.field, tally);
+        Results {
+            players: players.into_iter().collect(),
+            total: tally.total(),
+            top_ranks: self.config.top_ranks,
+            exhaustive,
+            names: self
+                .field
+                .iter()
+                .map(|player| player.name.clone())
+                .collect(),
+            above: tally.above.clone(),
+            distinct_standings: tally
+                .standings
+                .as_ref()
+                .and_then(|standings| self.distinct_standings(standings, tally.total())),
+        }
+    }
+
+    /// summarize the counts of each top-rank standing in `standings`, out of a total weight of
+    /// `total`; see [`DistinctStandings`]
+    fn distinct_standings(
+        &self,
+        standings: &HashMap<Vec<usize>, f64>,
+        total: f64,
+    ) -> Option<DistinctStandings> {
+        if total <= 0.0 {
+            return None;
+        }
+        // Of equally common standings, the first by player index is the mode, so that it doesn't
+        // depend on the order of the map.
+        let (top, count) = standings.iter().reduce(|mode, entry| {
+            match entry.1.total_cmp(mode.1).then_with(|| mode.0.cmp(entry.0)) {
+                Ordering::Greater => entry,
+                _ => mode,
+            }
+        })?;
+        Some(DistinctStandings {
+            count: standings.len(),
+            mode: top.iter().map(|&id| self.field[id].name.clone()).collect(),
+            probability: count / total,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+/// How many different orders of the players in the top ranks came up over a set of simulations,
+/// and the most common one
+///
+/// A most common standing with a probability near 1 means the event is all but decided.
+pub struct DistinctStandings {
+    /// number of different orders of the players in the top ranks
+    pub count: usize,
+    /// the most common order, as the names of the players from the first rank down
+    pub mode: Vec<String>,
+    /// the weighted share of the simulations with the most common order
+    pub probability: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+/// Final placements of each player over a set of simulations
+///
+/// Results serialize with serde as a struct of the players keyed by name, each with their record
+/// and placements (see [`Player`]), and the fields below, so that they can be saved and loaded
+/// again by other tools without running the simulations again. Players and placements are kept in
+/// order, so the same results always serialize the same way.
+pub struct Results {
+    /// players keyed by name, in name order, with their placement counts
+    players: BTreeMap<String, Player>,
+    /// total weight of all simulations
+    total: f64,
+    /// number of top ranks counted in each simulation
+    top_ranks: usize,
+    /// whether every possible outcome of the matches was simulated
+    exhaustive: bool,
+    /// names of the players of the simulated field, ordered by index
+    names: Vec<String>,
+    /// pairwise counts of the players finishing above each other; see [`Tally::with_pairwise`]
+    #[serde(default)]
+    above: Vec<f64>,
+    /// the number of different top-rank standings; see [`SimulatorBuilder::distinct_standings`]
+    #[serde(default)]
+    distinct_standings: Option<DistinctStandings>,
+}
+
+impl Results {
+    /// number of top ranks counted in each simulation
+    pub fn top_ranks(&self) -> usize {
+        self.top_ranks
+    }
+
+    /// whether every possible outcome of the matches was simulated, so that placements that
+    /// never or always happened are certain
+    pub fn is_exhaustive(&self) -> bool {
+        self.exhaustive
+    }
+
+    /// total weight of all simulations; with only 50/50 matches, this is the number of simulations
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// the placement counts of the player named `name`, keyed by rank
+    pub fn placements(&self, name: &str) -> Option<&BTreeMap<usize, f64>> {
+        self.players.get(name).map(|player| &player.placements)
+    }
+
+    /// all players with their placement counts, in name order
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.players.values()
+    }
+
+    /// the chance that the player named `name` finished above the player named `other`
+    ///
+    /// Returns None if pairwise counts were not tallied (see [`SimulatorBuilder::pairwise`]), if
+    /// either player is not in the simulated field, or if no simulations were counted.
+    pub fn finishes_above(&self, name: &str, other: &str) -> Option<f64> {
+        if self.above.is_empty() || self.total <= 0.0 {
+            return None;
+        }
+        let id = self.names.binary_search_by(|n| n.as_str().cmp(name)).ok()?;
+        let other_id = self
+            .names
+            .binary_search_by(|n| n.as_str().cmp(other))
+            .ok()?;
+        Some(self.above[id * self.names.len() + other_id] / self.total)
+    }
+
+    /// how many different orders of the players in the top ranks came up, and the most common
+    /// one
+    ///
+    /// Returns None if they were not counted (see [`SimulatorBuilder::distinct_standings`]), or if
+    /// no simulations were counted.
+    pub fn distinct_standings(&self) -> Option<&DistinctStandings> {
+        self.distinct_standings.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a player with the given record and opponents, and no opponent record
+    fn player(name: &str, wins: u32, losses: u32, opponents: &[&str]) -> Player {
+        let raw = RawPlayer {
+            name: String::from(name),
+            wins,
+            losses,
+            draws: 0,
+            byes: 0,
+            opponents: None,
+            opp_wins: 0,
+            opp_losses: 0,
+            rating: None,
+            game_wins: 0,
+            game_losses: 0,
+            opp_game_wins: 0,
+            opp_game_losses: 0,
+        };
+        Player::new(
+            raw,
+            opponents.iter().map(|&opp| String::from(opp)).collect(),
+        )
+    }
+
+    /// the players keyed by name, as loaded
+    fn players(list: Vec<Player>) -> HashMap<String, Player> {
+        list.into_iter()
+            .map(|player| (player.name.clone(), player))
+            .collect()
+    }
+
+    /// an undecided best-of-one match between `player1` and `player2`
+    fn pairing(player1: &str, player2: &str) -> Pairing {
+        Pairing {
+            player1: String::from(player1),
+            player2: String::from(player2),
+            others: Vec::new(),
+            p1_win: None,
+            winner: None,
+            best_of: None,
+        }
+    }
+
+    /// a small event of six players with a round of three matches left and one more match
+    fn event() -> (HashMap<String, Player>, Vec<Pairing>) {
+        let players = players(vec![
+            player("a", 2, 0, &["b", "c"]),
+            player("b", 1, 1, &["a", "d"]),
+            player("c", 1, 1, &["a", "e"]),
+            player("d", 1, 1, &["b", "f"]),
+            player("e", 1, 1, &["c", "f"]),
+            player("f", 0, 2, &["d", "e"]),
+        ]);
+        let matches = vec![
+            pairing("a", "d"),
+            pairing("b", "e"),
+            pairing("c", "f"),
+            pairing("a", "b"),
+        ];
+        (players, matches)
+    }
+
+    /// the name and placements of every player in `results`
+    fn placements(results: &Results) -> Vec<(String, BTreeMap<usize, f64>)> {
+        results
+            .players()
+            .map(|p| (p.name.clone(), p.placements.clone()))
+            .collect()
+    }
+
+    /// assert that every player has the same expected rank in `results` and `expected`, up to
+    /// rounding
+    fn assert_expected_ranks(results: &Results, expected: &Results) {
+        for (player, other) in results.players().zip(expected.players()) {
+            let (mean, deviation) = player.expected_rank.unwrap();
+            let (other_mean, other_deviation) = other.expected_rank.unwrap();
+            assert!((mean - other_mean).abs() < 1e-9, "{}", player.name);
+            assert!(
+                (deviation - other_deviation).abs() < 1e-9,
+                "{}",
+                player.name
+            );
+        }
+    }
+
+    /// the default settings of a [`SimulatorBuilder`]
+    fn config() -> SimConfig {
+        SimulatorBuilder::new(HashMap::new(), Vec::new()).config
+    }
+
+    #[test]
+    fn opponent_winrate_without_opponent_games() {
+        let mut field = index_players(&players(vec![
+            player("a", 2, 0, &["b", "c"]),
+            player("b", 0, 0, &["a"]),
+            player("c", 0, 0, &["a"]),
+        ]));
+        let base = field.clone();
+        for config in [
+            config(),
+            SimConfig {
+                trust_csv_opp: true,
+                winrate_floor: 0.0,
+                ..config()
+            },
+            SimConfig {
+                pooled_opp_winrate: true,
+                winrate_floor: 0.0,
+                ..config()
+            },
+        ] {
+            compute_opponent_winrates(&mut field, &base, config);
+            assert_eq!(field[0].opp_winrate, config.winrate_floor);
+        }
+    }
+
+    #[test]
+    fn nan_opponent_winrate_ranks_lowest() {
+        let config = SimConfig {
+            tiebreakers: Tiebreakers::from(Tiebreaker::OpponentWinrate),
+            ..config()
+        };
+        let mut field = index_players(&players(vec![
+            player("a", 1, 1, &[]),
+            player("b", 1, 1, &[]),
+        ]));
+        field[0].opp_winrate = f64::NAN;
+        field[1].opp_winrate = 0.0;
+        assert_eq!(rank_players(&field[0], &field[1], config), Ordering::Less);
+        assert_eq!(
+            rank_players(&field[1], &field[0], config),
+            Ordering::Greater
+        );
+        field[1].opp_winrate = f64::NAN;
+        assert_eq!(
+            rank_players(&field[0], &field[1], config),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn simulation_space_overflow() {
+        let names = (0..2 * usize::BITS)
+            .map(|i| format!("p{:03}", i))
+            .collect::<Vec<_>>();
+        let field = index_players(&players(
+            names.iter().map(|name| player(name, 0, 0, &[])).collect(),
+        ));
+        let pairings = names
+            .chunks(2)
+            .map(|pair| pairing(&pair[0], &pair[1]))
+            .collect::<Vec<_>>();
+        let matches = resolve_matches(&pairings, &field, false, 0.0);
+        let bits = usize::BITS as usize;
+        assert_eq!(
+            simulation_space(&matches[..bits - 1], false),
+            Some(1 << (bits - 1))
+        );
+        assert_eq!(simulation_space(&matches, false), None);
+        // A match that was already played adds no outcomes.
+        let mut decided = matches.clone();
+        decided[0].result = Some(Outcome::Player1Wins(0));
+        assert_eq!(simulation_space(&decided, false), Some(1 << (bits - 1)));
+    }
+
+    #[test]
+    fn results_do_not_depend_on_threads() {
+        let (players, matches) = event();
+        let run = |threads| {
+            Simulator::builder(players.clone(), matches.clone())
+                .top_ranks(3)
+                .threads(threads)
+                .build()
+                .run_exhaustive()
+                .unwrap()
+        };
+        let sequential = run(1);
+        for threads in [2, 3, 16] {
+            let results = run(threads);
+            assert_eq!(placements(&results), placements(&sequential));
+            assert_expected_ranks(&results, &sequential);
+        }
+    }
+
+    #[test]
+    fn elo_odds() {
+        assert_eq!(elo_win_prob(1500.0, 1500.0), 0.5);
+        let p = elo_win_prob(1500.0 + ELO_DIVISOR, 1500.0);
+        assert!((p / (1.0 - p) - ELO_BASE).abs() < 1e-9);
+        assert!((elo_win_prob(1500.0, 1500.0 + ELO_DIVISOR) - (1.0 - p)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn truncated_player_row() {
+        let data = "name,wins,losses,opp_wins,opp_losses\n\
+                    XX-Alice,2,0,1,3\n\
+                    XX-Bob,1\n";
+        let message = read_players(data.as_bytes(), 3, b',')
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("line: 3"), "{}", message);
+        assert!(
+            message.contains("found record with 2 fields"),
+            "{}",
+            message
+        );
+        let message = read_matches("player1,player2\nAlice\n".as_bytes(), 0, b',')
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("line: 2"), "{}", message);
+    }
+
+    #[test]
+    fn strip_multibyte_prefix() {
+        assert_eq!(strip_prefix(String::from("ÉÜ-Zoë"), 3), "Zoë");
+        assert_eq!(strip_prefix(String::from("日本-選手"), 3), "選手");
+        assert_eq!(strip_prefix(String::from("日本-"), 3), "");
+        assert_eq!(strip_prefix(String::from("日本"), 3), "日本");
+        assert_eq!(strip_prefix(String::from("Zoë"), 0), "Zoë");
+    }
+
+    #[test]
+    fn identical_records_rank_by_name() {
+        let config = config();
+        let field = index_players(&players(vec![
+            player("Bob", 2, 1, &[]),
+            player("Alice", 2, 1, &[]),
+            player("Carol", 2, 1, &[]),
+        ]));
+        let mut ranking = field.iter().collect::<Vec<_>>();
+        ranking.sort_by(|p1, p2| rank_players(p2, p1, config));
+        let names = ranking.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["Alice", "Bob", "Carol"]);
+
+        // Players that were never indexed share an index, and fall back on their names.
+        let (alice, bob) = (player("Alice", 2, 1, &[]), player("Bob", 2, 1, &[]));
+        assert_eq!(rank_players(&alice, &bob, config), Ordering::Greater);
+        assert_eq!(rank_players(&bob, &alice, config), Ordering::Less);
+
+        // The placements of a run don't change from one run to the next.
+        let (players, matches) = event();
+        let run = || {
+            Simulator::builder(players.clone(), matches.clone())
+                .top_ranks(2)
+                .threads(1)
+                .build()
+                .run_exhaustive()
+                .unwrap()
+        };
+        assert_eq!(placements(&run()), placements(&run()));
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let (players, matches) = event();
+        let simulator = Simulator::builder(players.clone(), matches.clone())
+            .top_ranks(3)
+            .threads(1)
+            .build();
+        let mut saved = Vec::new();
+        let mut stopped = None;
+        let checkpoint = simulator.checkpoint_sampled(100, 7);
+        simulator
+            .resume_until(
+                checkpoint,
+                30,
+                |checkpoint| {
+                    checkpoint.write(&mut saved).unwrap();
+                    stopped = Some(checkpoint.clone());
+                },
+                |_| true,
+            )
+            .unwrap();
+        let stopped = stopped.unwrap();
+        let checkpoint = Checkpoint::read(saved.as_slice()).unwrap();
+        assert_eq!(checkpoint.simulations(), 100);
+        assert_eq!(checkpoint.next(), 30);
+        assert_eq!(checkpoint.seed(), Some(7));
+        let mut written = Vec::new();
+        checkpoint.write(&mut written).unwrap();
+        assert_eq!(written, saved);
+
+        // The count, Welford means, and squared deviations are read back exactly.
+        assert_eq!(checkpoint.tally.total, stopped.tally.total);
+        assert_eq!(checkpoint.tally.rank_means, stopped.tally.rank_means);
+        assert_eq!(
+            checkpoint.tally.rank_deviations,
+            stopped.tally.rank_deviations
+        );
+
+        // Resuming the saved run gives the results of running it in one go.
+        let resumed = simulator.resume(checkpoint.clone(), 30, |_| {}).unwrap();
+        let whole = simulator.run_sampled(100, 7);
+        assert_eq!(placements(&resumed), placements(&whole));
+        assert_expected_ranks(&resumed, &whole);
+
+        // A checkpoint is only resumed with the same players and matches.
+        let mut other_matches = matches;
+        other_matches.pop();
+        let other = Simulator::builder(players, other_matches)
+            .top_ranks(3)
+            .build();
+        assert!(other.resume(checkpoint, 30, |_| {}).is_err());
+    }
+
+    #[test]
+    fn iteration_weights_sum_to_run_count() {
+        let (players, mut pairings) = event();
+        pairings[0].p1_win = Some(0.7);
+        pairings[1].best_of = Some(3);
+        pairings[2].winner = Some(String::from("c"));
+        let field = index_players(&players);
+        for draws in [false, true] {
+            let matches = resolve_matches(&pairings, &field, draws, 0.5);
+            let space = simulation_space(&matches, draws).unwrap();
+            let mut outcomes = vec![Outcome::Draw; matches.len()];
+            let total = (0..space)
+                .map(|i| iteration_outcomes(i, &matches, draws, &mut outcomes))
+                .sum::<f64>();
+            assert!(
+                (total - space as f64).abs() < 1e-9,
+                "{} != {}",
+                total,
+                space
+            );
+        }
+    }
+
+    #[test]
+    fn unweighted_counts_stay_whole() {
+        let mut tally = Tally::new(3, 2);
+        tally.add_ranking(&[2, 0, 1], &[], 1.0);
+        tally.add_ranking(&[0, 2, 1], &[], 1.0);
+        assert!(matches!(tally.counts, Counts::Whole(_)));
+        let mut weighted = Tally::new(3, 2);
+        weighted.add_ranking(&[0, 1, 2], &[], 0.5);
+        assert!(matches!(weighted.counts, Counts::Weighted(_)));
+        let tally = tally.merge(weighted);
+        assert!(matches!(tally.counts, Counts::Weighted(_)));
+        assert_eq!(
+            tally.placements(0).collect::<Vec<_>>(),
+            [(1, 1.5), (2, 1.0)]
+        );
+        assert_eq!(tally.total(), 2.5);
+    }
+
+    #[test]
+    fn gray_code_visits_every_outcome_once() {
+        let (players, mut pairings) = event();
+        pairings[1].best_of = Some(3);
+        pairings[2].winner = Some(String::from("c"));
+        let field = index_players(&players);
+        for draws in [false, true] {
+            let matches = resolve_matches(&pairings, &field, draws, 0.0);
+            let space = simulation_space(&matches, draws).unwrap();
+            let mut expected = (0..space)
+                .map(|i| {
+                    let mut outcomes = vec![Outcome::Draw; matches.len()];
+                    iteration_outcomes(i, &matches, draws, &mut outcomes);
+                    format!("{:?}", outcomes)
+                })
+                .collect::<Vec<_>>();
+            let mut code = GrayCode::new(0, &matches, draws);
+            let mut outcomes = vec![Outcome::Draw; matches.len()];
+            let mut visited = Vec::new();
+            loop {
+                let previous = outcomes.clone();
+                code.outcomes(&matches, &mut outcomes);
+                if !visited.is_empty() {
+                    let changed = (0..matches.len()).filter(|&m| outcomes[m] != previous[m]);
+                    assert_eq!(changed.count(), 1);
+                }
+                visited.push(format!("{:?}", outcomes));
+                if code.advance().is_none() {
+                    break;
+                }
+            }
+            // Starting partway, as each thread does, picks up the order where it is.
+            for (start, visit) in visited.iter().enumerate() {
+                GrayCode::new(start, &matches, draws).outcomes(&matches, &mut outcomes);
+                assert_eq!(&format!("{:?}", outcomes), visit);
+            }
+            expected.sort();
+            visited.sort();
+            assert_eq!(visited, expected);
+        }
+    }
+
+    #[test]
+    fn gray_code_run_matches_enumeration() {
+        let (players, mut matches) = event();
+        matches[0].p1_win = Some(0.7);
+        matches[1].best_of = Some(3);
+        for draws in [false, true] {
+            let simulator = Simulator::builder(players.clone(), matches.clone())
+                .top_ranks(3)
+                .draws(draws)
+                .threads(3)
+                .build();
+            let space = simulator.simulation_space().unwrap();
+            let gray = simulator.run_exhaustive().unwrap();
+            let enumerated = simulator.run_first(space);
+            for (player, other) in gray.players().zip(enumerated.players()) {
+                assert_eq!(player.placements.len(), other.placements.len());
+                for ((rank, count), (other_rank, other_count)) in
+                    player.placements.iter().zip(&other.placements)
+                {
+                    assert_eq!(rank, other_rank);
+                    assert!((count - other_count).abs() < 1e-9, "{}", player.name);
+                }
+            }
+            assert_expected_ranks(&gray, &enumerated);
+        }
+    }
+
+    /// the pairs of names in `pairs`, as string slices
+    fn names(pairs: &[(String, String)]) -> Vec<(&str, &str)> {
+        pairs
+            .iter()
+            .map(|(p1, p2)| (p1.as_str(), p2.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn swiss_bye_for_lowest_player() {
+        let players = players(vec![
+            player("a", 2, 0, &[]),
+            player("b", 1, 1, &[]),
+            player("c", 1, 1, &[]),
+            player("d", 0, 2, &[]),
+            player("e", 0, 2, &[]),
+        ]);
+        let pairs = swiss_pairings(&players, PointValues::default());
+        assert_eq!(names(&pairs), [("a", "b"), ("c", "d"), ("e", BYE)]);
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let players = players(vec![
+            player("a", 2, 0, &["b"]),
+            player("b", 2, 0, &["a"]),
+            player("c", 1, 1, &["d"]),
+            player("d", 1, 1, &["c"]),
+        ]);
+        let pairs = swiss_pairings(&players, PointValues::default());
+        assert_eq!(names(&pairs), [("a", "c"), ("b", "d")]);
+    }
+
+    #[test]
+    fn swiss_all_rematches_pairs_in_standings_order() {
+        // Everyone has played everyone, so every pairing is a rematch.
+        let players = players(vec![
+            player("a", 3, 0, &["b", "c", "d"]),
+            player("b", 2, 1, &["a", "c", "d"]),
+            player("c", 1, 2, &["a", "b", "d"]),
+            player("d", 0, 3, &["a", "b", "c"]),
+        ]);
+        let pairs = swiss_pairings(&players, PointValues::default());
+        assert_eq!(names(&pairs), [("a", "b"), ("c", "d")]);
+    }
+
+    #[test]
+    fn swiss_equal_scores_pair_by_name() {
+        let players = players(vec![
+            player("dave", 1, 1, &[]),
+            player("bob", 1, 1, &[]),
+            player("carol", 1, 1, &[]),
+            player("alice", 1, 1, &[]),
+        ]);
+        let pairs = swiss_pairings(&players, PointValues::default());
+        assert_eq!(names(&pairs), [("alice", "bob"), ("carol", "dave")]);
+        let mut matches = Vec::new();
+        assert_eq!(add_pairings(&mut matches, &pairs), 2);
+        // Pairing the same round again adds nothing.
+        assert_eq!(add_pairings(&mut matches, &pairs), 0);
+    }
+
+    #[test]
+    fn opponent_winrates_differing_in_seventh_digit() {
+        let config = SimConfig {
+            tiebreakers: Tiebreakers::from(Tiebreaker::OpponentWinrate),
+            trust_csv_opp: true,
+            winrate_floor: 0.0,
+            ..config()
+        };
+        let mut a = player("a", 2, 1, &[]);
+        (a.opp_wins, a.opp_losses) = (3_333_333, 6_666_667);
+        let mut b = player("b", 2, 1, &[]);
+        (b.opp_wins, b.opp_losses) = (3_333_334, 6_666_666);
+        let mut field = index_players(&players(vec![a, b]));
+        let base = field.clone();
+        compute_opponent_winrates(&mut field, &base, config);
+        assert!(field[0].opp_winrate < field[1].opp_winrate);
+        assert_eq!(
+            rank_players(&field[1], &field[0], config),
+            Ordering::Greater
+        );
+        assert_eq!(cmp_winrate(0.1234567, 0.1234568), Ordering::Less);
+    }
+
+    #[test]
+    fn win_percentage_favors_fewer_games() {
+        let field = index_players(&players(vec![
+            player("a", 3, 0, &[]),
+            player("b", 4, 2, &[]),
+        ]));
+        let points = config();
+        assert_eq!(rank_players(&field[0], &field[1], points), Ordering::Less);
+        let winpct = SimConfig {
+            rank_by: RankBy::WinPercentage,
+            ..config()
+        };
+        assert_eq!(
+            rank_players(&field[0], &field[1], winpct),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn tiebreakers_chain_exactly_the_list() {
+        let chain =
+            |list: &[Tiebreaker]| Tiebreakers::new(list).unwrap().iter().collect::<Vec<_>>();
+        assert_eq!(
+            chain(&[Tiebreaker::OpponentWinrate]),
+            [Tiebreaker::OpponentWinrate]
+        );
+        assert_eq!(
+            chain(&[Tiebreaker::GameWinrate, Tiebreaker::HeadToHead]),
+            [Tiebreaker::GameWinrate, Tiebreaker::HeadToHead]
+        );
+        assert_eq!(
+            Tiebreakers::default().iter().collect::<Vec<_>>(),
+            DEFAULT_TIEBREAKERS
+        );
+        assert_eq!(
+            "oppwr"
+                .parse::<Tiebreakers>()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            [Tiebreaker::OpponentWinrate]
+        );
+    }
+
+    #[test]
+    fn case_collision_names_both_players() {
+        let mut field = players(vec![
+            player("alice", 1, 0, &["Bob"]),
+            player("Alice", 0, 1, &[]),
+            player("bob", 0, 1, &["ALICE"]),
+        ]);
+        assert_eq!(
+            fold_player_case(&mut field).unwrap_err(),
+            CaseCollision {
+                first: String::from("Alice"),
+                second: String::from("alice"),
+            }
+        );
+
+        field.remove("Alice");
+        let case = fold_player_case(&mut field).unwrap();
+        assert_eq!(case.spelling(String::from("ALICE")), "alice");
+        assert_eq!(field["alice"].opponents, ["bob"]);
+        assert_eq!(field["bob"].opponents, ["alice"]);
+    }
+
+    #[test]
+    fn spaced_names_match_clean_ones() {
+        assert_eq!(normalize_name("  Alice Smith "), "Alice Smith");
+        assert_eq!(normalize_name(" \"Alice \t Smith\" "), "Alice Smith");
+        assert_eq!(normalize_name("Alice Smith"), "Alice Smith");
+
+        let data = "name,wins,losses,opponents,opp_wins,opp_losses\n\
+                    \"  XX-Alice  \",1,0, XX-Bob ,0,1\n\
+                    XX-Bob,0,1,XX-Alice,1,0\n";
+        let field = read_players(data.as_bytes(), 3, b',').unwrap();
+        assert_eq!(field["Alice"].opponents, ["Bob"]);
+        assert_eq!(field["Bob"].opponents, ["Alice"]);
+        let names = |data: &str| {
+            let matches = read_matches(data.as_bytes(), 3, b',').unwrap();
+            let pairing = &matches[0];
+            (pairing.player1.clone(), pairing.player2.clone())
+        };
+        assert_eq!(
+            names("player1,player2\n XX-Alice ,XX-Bob  \n"),
+            names("player1,player2\nXX-Alice,XX-Bob\n")
+        );
+    }
+
+    #[test]
+    fn split_ties_share_placements() {
+        let counts = |tally: &Tally, id| tally.placements(id).collect::<Vec<_>>();
+        let mut tally = Tally::new(5, 3);
+
+        // Players 1, 2, and 3 are tied for ranks 2 to 4, of which only 2 and 3 are counted.
+        tally.add_ranking(&[0, 1, 2, 3, 4], &[false, true, true, false], 1.0);
+        assert_eq!(counts(&tally, 0), [(1, 1.0)]);
+        for id in 1..4 {
+            assert_eq!(counts(&tally, id), [(2, 1.0 / 3.0), (3, 1.0 / 3.0)]);
+        }
+        assert_eq!(counts(&tally, 4), []);
+
+        // Without ties, the ranking counts as it is, with its weight.
+        tally.add_ranking(&[4, 3, 2, 1, 0], &[], 3.0);
+        assert_eq!(counts(&tally, 4), [(1, 3.0)]);
+        assert_eq!(counts(&tally, 3), [(2, 3.0 + 1.0 / 3.0), (3, 1.0 / 3.0)]);
+        assert_eq!(tally.total, 4.0);
+
+        // A tie for first place among everyone shares every counted rank.
+        let mut tally = Tally::new(2, 2);
+        tally.add_ranking(&[0, 1], &[true, false], 1.0);
+        assert_eq!(counts(&tally, 0), [(1, 0.5), (2, 0.5)]);
+        assert_eq!(counts(&tally, 1), [(1, 0.5), (2, 0.5)]);
+
+        // Split placements still add up to one per counted rank.
+        let sum = |tally: &Tally| {
+            (0..2)
+                .flat_map(|id| tally.placements(id))
+                .map(|(_, count)| count)
+                .sum::<f64>()
+        };
+        assert_eq!(sum(&tally), 2.0);
+    }
+
+    #[test]
+    fn merged_tallies_match_one_sequential_tally() {
+        let (players, pairings) = event();
+        let field = index_players(&players);
+        let matches = resolve_matches(&pairings, &field, false, 0.0);
+        let config = SimConfig {
+            top_ranks: 3,
+            pairwise: true,
+            ..config()
+        };
+        let space = simulation_space(&matches, false).unwrap();
+        let run = |range: std::ops::Range<usize>, tally: &mut Tally| {
+            let mut scratch = Scratch::new(matches.len(), &field);
+            for i in range {
+                let weight = iteration_outcomes(i, &matches, false, &mut scratch.outcomes);
+                simulate(config, &matches, &field, weight, &mut scratch, tally);
+            }
+        };
+
+        let sequential = run_in_chunks(space, 1, config, &field, run);
+        let mut first = Tally::for_config(&field, config);
+        let mut second = Tally::for_config(&field, config);
+        run(0..space / 3, &mut first);
+        run(space / 3..space, &mut second);
+        let merged = first.merge(second);
+
+        assert_eq!(merged.total, sequential.total);
+        assert!(matches!(merged.counts, Counts::Whole(_)));
+        for i in 0..field.len() * config.top_ranks {
+            assert_eq!(merged.counts.get(i), sequential.counts.get(i));
+        }
+        for id in 0..field.len() {
+            assert!((merged.rank_means[id] - sequential.rank_means[id]).abs() < 1e-12);
+            assert!((merged.rank_deviations[id] - sequential.rank_deviations[id]).abs() < 1e-9);
+        }
+        assert_eq!(merged.above, sequential.above);
+    }
+
+    #[test]
+    fn json_errors_give_the_line_of_their_object() {
+        let line = |data: &str| match read_players_json(data.as_bytes(), 3) {
+            Err(JsonError::Invalid { line, .. }) => line,
+            result => panic!("expected invalid data, got {:?}", result.map(|p| p.len())),
+        };
+        let players = "[\n\
+                       {\"name\": \"XX-Alice\", \"wins\": 1, \"losses\": 0, \"opp_wins\": 0,\
+                        \"opp_losses\": 1},\n\
+                       {\"name\": \"XX-Bob\",\n\
+                        \"wins\": -1, \"losses\": 0, \"opp_wins\": 1, \"opp_losses\": 0}\n\
+                       ]";
+        assert_eq!(line(players), 3);
+        assert_eq!(line("[\n{\"name\": \"XX-Alice\"},\n3]"), 3);
+        assert_eq!(line("[\n{\"name\": \"XX-Alice\",\n\"wins\": 1,}]"), 3);
+
+        let players = "[{\"name\": \"XX-Alice\", \"wins\": 1, \"losses\": 0,\
+                       \"opp_wins\": 0, \"opp_losses\": 1, \"opponents\": [\"XX-Bob\", null]}]";
+        let players = read_players_json(players.as_bytes(), 3).unwrap();
+        assert_eq!(players["Alice"].opponents, ["Bob"]);
+    }
+
+    #[test]
+    fn half_point_bye_ranks_below_a_win() {
+        let half = PointValues {
+            bye: 1.5,
+            ..PointValues::default()
+        };
+
+        // A bye already in the players file scores half a win.
+        let mut a = player("a", 2, 1, &[]);
+        a.byes = 1;
+        let field = index_players(&players(vec![a, player("b", 2, 1, &[])]));
+        assert_eq!(field[0].points(half), 4.5);
+        assert_eq!(field[0].points(PointValues::default()), 6.0);
+        let config = SimConfig {
+            points: half,
+            ..config()
+        };
+        assert_eq!(rank_players(&field[0], &field[1], config), Ordering::Less);
+
+        // So does a simulated bye, which leaves b, who won a real match, always in first place.
+        let field = players(vec![
+            player("a", 1, 1, &[]),
+            player("b", 1, 1, &[]),
+            player("c", 1, 1, &[]),
+        ]);
+        let mut won = pairing("b", "c");
+        won.winner = Some(String::from("b"));
+        let results = Simulator::builder(field, vec![pairing("a", BYE), won])
+            .top_ranks(1)
+            .points(half)
+            .build()
+            .run_exhaustive()
+            .unwrap();
+        assert!(!results.placements("b").unwrap().is_empty());
+        assert!(results.placements("a").unwrap().is_empty());
+        assert!(results.placements("c").unwrap().is_empty());
+    }
+
+    #[test]
+    fn pooled_opponent_winrate_discounts_byes() {
+        // c beat a, whose two wins include a bye.
+        let mut a = player("a", 2, 1, &["c"]);
+        a.byes = 1;
+        let field = index_players(&players(vec![a, player("c", 1, 0, &["a"])]));
+        let winrate = |bye, pooled_opp_winrate| {
+            let config = SimConfig {
+                points: PointValues {
+                    bye,
+                    ..PointValues::default()
+                },
+                pooled_opp_winrate,
+                winrate_floor: 0.0,
+                ..config()
+            };
+            let mut players = field.clone();
+            compute_opponent_winrates(&mut players, &field, config);
+            players[1].opp_winrate
+        };
+        // Without the match against c, a is 2-0, or 1.5-0 with a half-point bye.
+        assert_eq!(winrate(3.0, true), 1.0);
+        assert_eq!(winrate(1.5, true), 0.75);
+        // With it, a is 2-1, or 1.5-1.
+        assert_eq!(winrate(3.0, false), 2.0 / 3.0);
+        assert_eq!(winrate(1.5, false), 0.5);
+    }
+}
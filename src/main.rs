@@ -2,276 +2,2712 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+mod progress;
+
+/// Log a note about how the run goes, such as the seed it samples with, which is written unless
+/// the run is quiet; see [`init_logger`].
+macro_rules! note {
+    ($($arg:tt)*) => { log::info!(target: NOTE_TARGET, $($arg)*) };
+}
+
 use {
-    argh::{self, FromArgs},
-    csv,
-    serde::Deserialize,
-    std::cmp::Ordering,
-    std::collections::{HashMap, HashSet},
+    argh::FromArgs,
+    ccls_sim::{
+        add_pairings, add_round_robin, check_opponent_lists, check_opponent_records,
+        fold_match_case, fold_player_case, read_matches, read_matches_json, read_players,
+        read_players_json, swiss_pairings, unknown_players, Checkpoint, Destiny, JsonError,
+        MatchImpact, Outcome, Player, PointValues, RankBy, Replay, Results, Simulator,
+        SimulatorBuilder, Standings, Status, Tiebreaker, Tiebreakers, BYE, DEFAULT_WINRATE_FLOOR,
+    },
+    flate2::read::GzDecoder,
+    log::{debug, info, warn, Level, LevelFilter},
+    owo_colors::{AnsiColors, OwoColorize, Stream},
+    progress::{format_duration, Progress},
+    serde::{ser::SerializeMap, Serialize, Serializer},
+    serde_json::value::RawValue,
+    std::{
+        cell::Cell,
+        cmp::Ordering,
+        collections::HashMap,
+        io::IsTerminal,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
 };
 
-#[derive(Debug, Deserialize)]
-/// Player data loaded from CSV
-struct RawPlayer {
-    /// name of the player
-    name: String,
-    /// number of wins by the player
-    wins: u8,
-    /// number of losses by the player
-    losses: u8,
-    /// first opponent
-    opp1: String,
-    /// second opponent
-    opp2: String,
-    /// third opponent
-    opp3: String,
-    /// fourth opponent
-    opp4: String,
-    /// total wins by all opponents, excluding wins against the player
-    opp_wins: u8,
-    /// total losses by all opponents, excluding losses against the player
-    opp_losses: u8,
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Format of the results written by the simulator
+enum OutputFormat {
+    /// human-readable text
+    Text,
+    /// a JSON array with one object per player
+    Json,
+    /// CSV with one row per player and rank
+    Csv,
+    /// a Markdown table with one row per player and one column per rank
+    Markdown,
+    /// CSV with one row per player and one column per rank, for heatmaps
+    RankMatrix,
+    /// CSV with one row per player and their summary statistics, without their placements
+    Summary,
 }
 
-#[derive(Clone, Debug)]
-/// Internal representation of a Player
-struct Player {
-    /// name of the player
-    name: String,
-    /// number of wins by the player
-    wins: u8,
-    /// number of losses by the player
-    losses: u8,
-    /// total wins by all opponents, excluding wins against the player
-    opp_wins: u8,
-    /// total losses by all opponents, excluding losses against the player
-    opp_losses: u8,
-    /// list of opponents
-    opponents: Vec<String>,
-    /// counts of placements by the player, keyed by rank
-    placements: HashMap<usize, usize>,
-}
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
 
-impl Player {
-    /// create a new Player from a RawPlayer
-    fn new(data: RawPlayer) -> Player {
-        Player {
-            name: data.name,
-            wins: data.wins,
-            losses: data.losses,
-            opp_wins: data.opp_wins,
-            opp_losses: data.opp_losses,
-            opponents: vec![data.opp1, data.opp2, data.opp3, data.opp4],
-            placements: HashMap::new(),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "md" => Ok(OutputFormat::Markdown),
+            "rank-matrix" => Ok(OutputFormat::RankMatrix),
+            "summary" => Ok(OutputFormat::Summary),
+            _ => Err(format!(
+                "unknown output format '{}', expected one of: text, json, csv, md, rank-matrix, \
+                 summary",
+                s
+            )),
         }
     }
+}
 
-    /// add a final placement for the player
-    fn add_placement(&mut self, place: usize) {
-        *self.placements.entry(place).or_insert(0) += 1;
-    }
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Format of the players and matches files
+enum InputFormat {
+    /// CSV, or another delimiter set with --delimiter
+    Csv,
+    /// a JSON array with one object per row
+    Json,
+}
 
-    fn add_win(&mut self) {
-        self.wins += 1;
-    }
+impl std::str::FromStr for InputFormat {
+    type Err = String;
 
-    fn add_loss(&mut self) {
-        self.losses += 1;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "json" => Ok(InputFormat::Json),
+            _ => Err(format!(
+                "unknown input format '{}', expected one of: csv, json",
+                s
+            )),
+        }
     }
+}
 
-    fn add_opponent_win(&mut self) {
-        self.opp_wins += 1;
-    }
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// What to do with a player that is in more than one players file
+enum DuplicatePolicy {
+    /// fail
+    Error,
+    /// add up the records of all of the player's rows
+    Merge,
+    /// keep the row in the last file
+    Last,
+}
 
-    fn add_opponent_loss(&mut self) {
-        self.opp_losses += 1;
+impl std::str::FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(DuplicatePolicy::Error),
+            "merge" => Ok(DuplicatePolicy::Merge),
+            "last" => Ok(DuplicatePolicy::Last),
+            _ => Err(format!(
+                "unknown duplicate policy '{}', expected one of: error, merge, last",
+                s
+            )),
+        }
     }
 }
 
-#[derive(Debug, Deserialize)]
-/// A match between two players
-struct Match {
-    player1: String,
-    player2: String,
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Field delimiter of the input files
+struct Delimiter(u8);
+
+impl std::str::FromStr for Delimiter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            b"tab" => Ok(Delimiter(b'\t')),
+            &[c] => Ok(Delimiter(c)),
+            _ => Err(format!(
+                "invalid delimiter '{}', expected a single ASCII character or tab",
+                s
+            )),
+        }
+    }
 }
 
 #[derive(Debug, FromArgs)]
 /// CC Listener Series simulator
+// Options for loading and ranking the players come before the command, and the options of each
+// command after it. Without a command, the matches are simulated as by simulate.
 struct Opts {
     #[argh(option, short = 'p')]
-    /// filename with CSV data of players
-    players: String,
+    /// filename with CSV data of players, or - for stdin; may be given more than once to merge
+    /// several files
+    players: Vec<String>,
+
+    #[argh(option, default = "DuplicatePolicy::Error")]
+    /// what to do with a player in more than one --players file: error, merge to add up their
+    /// records, or last to keep the one in the last file (default: error)
+    on_duplicate: DuplicatePolicy,
 
     #[argh(option, short = 'm')]
-    /// filename with CSV data of matches
-    matches: String,
+    /// filename with CSV data of matches, or - for stdin; may be left out with --round-robin or
+    /// --swiss
+    matches: Option<String>,
+
+    #[argh(option)]
+    /// add a match between every two players of this group, unless they already have one: a
+    /// comma-separated list of player names, or a file listing them
+    round_robin: Option<String>,
+
+    #[argh(switch)]
+    /// pair the next Swiss round from the standings in the players file, avoiding rematches, and
+    /// simulate that round instead of a matches file
+    swiss: bool,
+
+    #[argh(option, default = "3")]
+    /// number of characters to strip from the start of every player name, such as a league code;
+    /// 0 keeps names as they are (default: 3)
+    name_prefix_len: usize,
+
+    #[argh(switch)]
+    /// match player names regardless of case, spelling each as in the players file
+    ignore_case: bool,
+
+    #[argh(option, default = "InputFormat::Csv")]
+    /// format of the players and matches files: csv, or json for an array of objects with the
+    /// same fields as the csv columns (default: csv)
+    input_format: InputFormat,
+
+    #[argh(option, default = "Delimiter(b',')")]
+    /// field delimiter of the players and matches files: a single character, or tab
+    /// (default: ,)
+    delimiter: Delimiter,
 
     #[argh(option, short = 'o')]
     /// filename for writing output (default: stdout)
     output: Option<String>,
 
+    #[argh(switch, short = 'q')]
+    /// write nothing to stderr but errors: no progress, notes, warnings, or info
+    quiet: bool,
+
+    #[argh(option, short = 'j')]
+    /// number of threads to run simulations on (default: number of CPUs)
+    threads: Option<usize>,
+
+    #[argh(option, short = 't')]
+    /// number of top ranks to compute in each simulation; required unless --top-percent is given
+    top_ranks: Option<usize>,
+
+    #[argh(option)]
+    /// compute the top ranks of this percentage of the players, rounded up, instead of a fixed
+    /// --top-ranks
+    top_percent: Option<f64>,
+
+    #[argh(switch)]
+    /// allow matches to be drawn; the players file may have a draws column
+    draws: bool,
+
+    #[argh(option, default = "0.0")]
+    /// in matches without a win probability or ratings, favor the player with more wins before
+    /// the round: the chance to win is a logistic function of the difference in those wins times
+    /// this factor (default: 0, for 50/50)
+    prior_momentum: f64,
+
+    #[argh(option, default = "3.0")]
+    /// match points for a win, which players are ranked by (default: 3)
+    win_points: f64,
+
+    #[argh(option, default = "1.0")]
+    /// match points for a draw (default: 1)
+    draw_points: f64,
+
+    #[argh(option, default = "0.0")]
+    /// match points for a loss (default: 0)
+    loss_points: f64,
+
+    #[argh(option)]
+    /// match points for a bye, in the matches or in the byes column of the players file, which
+    /// may be a fraction such as 1.5 for half a win (default: --win-points)
+    bye_points: Option<f64>,
+
+    #[argh(option, default = "RankBy::Points")]
+    /// what to rank players by before tiebreakers: points for match
+    /// points, or winpct for match win percentage (default: points)
+    rank_by: RankBy,
+
+    #[argh(switch)]
+    /// keep the opp_wins and opp_losses columns, updated by each simulated match, instead of
+    /// recomputing opponent records from the simulated standings
+    trust_csv_opp: bool,
+
+    #[argh(option, default = "DEFAULT_WINRATE_FLOOR")]
+    /// lowest winrate, from 0 to 1, that each opponent counts with in opponent winrates
+    /// (default: 0.33)
+    winrate_floor: f64,
+
+    #[argh(switch)]
+    /// compute opponent winrates from the combined record of all opponents, as older versions
+    /// did, instead of averaging the winrate of each opponent
+    pooled_opp_winrate: bool,
+
+    #[argh(option)]
+    /// chain of tiebreakers as a comma-separated list tried in order: h2h, oppwr, buchholz,
+    /// median-buchholz, gwr, losses, oppoppwr, or oppgwr (default: h2h,oppwr,oppoppwr)
+    tiebreakers: Option<Tiebreakers>,
+
+    #[argh(option)]
+    /// deprecated name for --tiebreakers
+    tiebreaker: Option<Tiebreakers>,
+
+    #[argh(switch)]
+    /// let players still tied after every tiebreaker share the placements of the ranks they
+    /// cover, instead of ordering them by name
+    split_ties: bool,
+
+    #[argh(switch)]
+    /// fail instead of warning when the matches name players that are not in the players file
+    strict: bool,
+
+    #[argh(switch)]
+    /// warn about players whose opp_wins and opp_losses don't add up from the records of their
+    /// listed opponents
+    validate: bool,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+
+    #[argh(switch)]
+    /// print the version and the git commit of the build, and exit
+    version: bool,
+}
+
+impl Opts {
+    /// the number of top ranks to compute, from --top-ranks or, once the players are loaded,
+    /// --top-percent
+    fn top_ranks(&self) -> usize {
+        self.top_ranks
+            .expect("--top-percent is resolved after loading the players")
+    }
+
+    /// the match points for each result, from --win-points, --draw-points, --loss-points, and
+    /// --bye-points
+    fn point_values(&self) -> PointValues {
+        PointValues {
+            win: self.win_points,
+            draw: self.draw_points,
+            loss: self.loss_points,
+            bye: self.bye_points.unwrap_or(self.win_points),
+        }
+    }
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+// The options are parsed once, so the size of the simulate variant doesn't matter.
+#[allow(clippy::large_enum_variant)]
+/// What to do with the loaded players and matches
+enum Command {
+    Simulate(SimulateOpts),
+    DryRun(DryRunOpts),
+    Analyze(AnalyzeOpts),
+}
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "simulate")]
+/// simulate the matches and write the placements of each player (the default command)
+struct SimulateOpts {
     #[argh(option, short = 'n')]
     /// number of simulations to run (default: all)
     simulation_count: Option<usize>,
 
-    #[argh(option, short = 't')]
-    /// number of top ranks to compute in each simulation
-    top_ranks: usize,
+    #[argh(option, short = 's')]
+    /// number of random simulations to sample instead of simulating all outcomes
+    sample: Option<usize>,
+
+    #[argh(option)]
+    /// sample until the 95% margin of error of the chance of a top finish, in percentage points,
+    /// is below this for every player (or every --player); --sample sets the most samples to draw
+    /// (default: 10000000)
+    target_error: Option<f64>,
+
+    #[argh(option)]
+    /// stop simulating after this many seconds, and write the results of the simulations run so
+    /// far, as for --sample
+    max_runtime: Option<f64>,
+
+    #[argh(option)]
+    /// seed for the random number generator used by --sample (default: random)
+    seed: Option<u64>,
+
+    #[argh(switch)]
+    /// write the current standings, before any of the remaining matches, instead of simulating
+    baseline: bool,
+
+    #[argh(option)]
+    /// only replay this simulation, by its index in the run (with --sample, also give --seed),
+    /// and write how each match went and the standings it led to
+    inspect: Option<usize>,
+
+    #[argh(option, default = "10000")]
+    /// report progress on stderr every N simulations when stderr is not a terminal, which shows a
+    /// progress bar instead; 0 disables progress reports (default: 10000)
+    progress_interval: usize,
+
+    #[argh(option)]
+    /// save the progress of the run to this file every --checkpoint-interval simulations, so that
+    /// it can be resumed with --resume
+    checkpoint: Option<String>,
+
+    #[argh(option, default = "1000000")]
+    /// number of simulations between saves of --checkpoint (default: 1000000)
+    checkpoint_interval: usize,
+
+    #[argh(option)]
+    /// resume the run saved in this checkpoint file, which must be for the same options
+    resume: Option<String>,
+
+    #[argh(option)]
+    /// number of top ranks to sum up for each player's chance of a top finish, up to --top-ranks
+    /// (default: --top-ranks)
+    top_k: Option<usize>,
+
+    #[argh(option, short = 'f', default = "OutputFormat::Text")]
+    /// output format: text, json, csv, md, rank-matrix, or summary (default: text)
+    format: OutputFormat,
+
+    #[argh(option)]
+    /// also write the summary statistics of each player, one row per player, to this file, as
+    /// with --format summary
+    summary_output: Option<String>,
+
+    #[argh(option)]
+    /// also write the placements of each player at every rank to this file, as with --format
+    /// rank-matrix
+    detail_output: Option<String>,
+
+    #[argh(option)]
+    /// order of the players: rank1, topk, expected, or name (default: topk, or with --all the
+    /// most likely finishing position, and for rank-matrix expected)
+    sort: Option<PlayerOrder>,
+
+    #[argh(switch)]
+    /// write placements as percentages of the simulations run (always on with --sample)
+    percent: bool,
+
+    #[argh(option, default = "2")]
+    /// number of decimal places for percentages and expected counts (default: 2)
+    precision: usize,
+
+    #[argh(switch)]
+    /// include players without any top placements, with a placement of 0 at every rank
+    include_empty: bool,
+
+    #[argh(switch)]
+    /// in json output, write each placement as its count and its probability at full precision,
+    /// instead of rounded
+    raw: bool,
+
+    #[argh(switch)]
+    /// only write the players who never finished in the top ranks, who are eliminated if every
+    /// outcome was simulated
+    eliminated: bool,
+
+    #[argh(option)]
+    /// only write the results of this player, by their name as written in the results; may be
+    /// given more than once (default: all players)
+    player: Vec<String>,
+
+    #[argh(switch)]
+    /// list every player, ordered by their most likely finishing position, including players
+    /// without any top placements
+    all: bool,
+
+    #[argh(switch)]
+    /// instead of placements, write a CSV matrix with the percentage of simulations in which each
+    /// written player finished above each other one; this takes time and memory proportional to
+    /// the square of the number of players
+    matrix: bool,
+
+    #[argh(switch)]
+    /// instead of placements, draw a bar chart of how often each --player finished at each rank
+    histogram: bool,
+
+    #[argh(switch)]
+    /// also report on stderr how many different orders of the top ranks came up, and the most
+    /// common one with its chance
+    distinct_standings: bool,
+
+    #[argh(switch)]
+    /// write the expected finishing rank of each player, over all ranks, with its standard
+    /// deviation
+    expected_rank: bool,
+
+    #[argh(switch)]
+    /// write the volatility of each player: the variance of their finishing rank over all ranks,
+    /// which is highest for the players most affected by the remaining results
+    volatility: bool,
 }
 
-fn strip_prefix(s: String, prefix_length: usize) -> String {
-    if s.len() >= prefix_length {
-        String::from(&s[prefix_length..])
-    } else {
-        s
-    }
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "dryrun")]
+/// report the number of simulations that simulate would run with these options, and a rough
+/// estimate of the time they would take, without running them
+struct DryRunOpts {
+    #[argh(option, short = 'n')]
+    /// number of simulations to run (default: all)
+    simulation_count: Option<usize>,
+
+    #[argh(option, short = 's')]
+    /// number of random simulations to sample instead of simulating all outcomes
+    sample: Option<usize>,
+
+    #[argh(option)]
+    /// sample until the 95% margin of error of the chance of a top finish, in percentage points,
+    /// is below this for every player (or every --player); --sample sets the most samples to draw
+    /// (default: 10000000)
+    target_error: Option<f64>,
 }
 
-fn opponent_winrate(p: &Player) -> f32 {
-    (p.opp_wins as f32) / ((p.opp_wins + p.opp_losses) as f32)
+#[derive(Debug, FromArgs)]
+#[argh(subcommand, name = "analyze")]
+/// check which outcomes of a player's matches guarantee them a top finish, whatever the outcomes
+/// of the other matches
+struct AnalyzeOpts {
+    #[argh(positional)]
+    /// name of the player, as written in the results
+    player: String,
+
+    #[argh(switch)]
+    /// instead, write how the player's chance of a top finish changes with the outcome of each
+    /// remaining match, most decisive first
+    match_impact: bool,
 }
 
-/// Order players first by totals wins, then by opponent winrate
-fn rank_players(p1: &Player, p2: &Player) -> Ordering {
-    let p1_oppwr = opponent_winrate(p1);
-    let p2_oppwr = opponent_winrate(p2);
-    p1.wins
-        .cmp(&p2.wins)
-        .then(p1_oppwr.partial_cmp(&p2_oppwr).unwrap())
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Category of an error that stops the run, which sets the exit status
+enum ErrorKind {
+    /// invalid or conflicting options
+    Usage,
+    /// a file that can't be opened, read, or written
+    Io,
+    /// input data that is invalid, or doesn't fit the options
+    Data,
 }
 
-/// Read in player data from `rdr`.
-///
-/// Returns a map of Player data keyed by player name.
-fn read_players(rdr: impl std::io::Read) -> HashMap<String, Player> {
-    let mut players = HashMap::new();
-    let mut reader = csv::Reader::from_reader(rdr);
-    for row in reader.deserialize() {
-        let mut player: RawPlayer = row.unwrap();
-        player.name = strip_prefix(player.name, 3);
-        player.opp1 = strip_prefix(player.opp1, 3);
-        player.opp2 = strip_prefix(player.opp2, 3);
-        player.opp3 = strip_prefix(player.opp3, 3);
-        player.opp4 = strip_prefix(player.opp4, 3);
-        players.insert(player.name.clone(), Player::new(player));
+impl ErrorKind {
+    /// the exit status for errors of this kind
+    fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Usage => 2,
+            ErrorKind::Io => 3,
+            ErrorKind::Data => 4,
+        }
     }
-    players
 }
 
-/// Read in match data from `rdr`.
-///
-/// Match data may contain duplicates, e.g. with opponents swapped.
-/// Returns a vector of matches, with duplicates removed.
-fn read_matches(rdr: impl std::io::Read) -> Vec<(String, String)> {
-    let mut matches = HashSet::new();
-    let mut reader = csv::Reader::from_reader(rdr);
-    for row in reader.deserialize() {
-        let match_: Match = row.unwrap();
-        let player1 = strip_prefix(match_.player1, 3);
-        let player2 = strip_prefix(match_.player2, 3);
-        matches.insert(if player1.cmp(&player2) == Ordering::Greater {
-            (player1, player2)
-        } else {
-            (player2, player1)
-        });
-    }
-    // Sort the matches to get deterministic simulations when a subset of simulations are run.
-    let mut result = matches.into_iter().collect::<Vec<_>>();
-    result.sort_unstable();
-    result
+#[derive(Debug)]
+/// An error that stops the run
+struct Error {
+    kind: ErrorKind,
+    message: String,
 }
 
-fn write_results(players: &Vec<&Player>, mut w: Box<dyn std::io::Write>) {
-    write!(w, "final players:\n").unwrap();
-    for player in players {
-        if player.placements.len() > 0 {
-            write!(w, "  {}: {:?}\n", player.name, player.placements).unwrap();
+impl Error {
+    fn usage(message: impl Into<String>) -> Error {
+        Error {
+            kind: ErrorKind::Usage,
+            message: message.into(),
+        }
+    }
+
+    fn io(message: impl Into<String>) -> Error {
+        Error {
+            kind: ErrorKind::Io,
+            message: message.into(),
+        }
+    }
+
+    fn data(message: impl Into<String>) -> Error {
+        Error {
+            kind: ErrorKind::Data,
+            message: message.into(),
+        }
+    }
+
+    /// an error `e` reading an input file, which is a data error if the file is invalid, such as a
+    /// corrupt or truncated gzip file, and an I/O error otherwise
+    fn read(message: String, e: &std::io::Error) -> Error {
+        match e.kind() {
+            std::io::ErrorKind::InvalidData
+            | std::io::ErrorKind::InvalidInput
+            | std::io::ErrorKind::UnexpectedEof => Error::data(message),
+            _ => Error::io(message),
+        }
+    }
+
+    /// an error in the JSON file at `path`, which is a data error unless reading the file failed;
+    /// see [`Error::read`]
+    fn json(path: &str, e: &JsonError) -> Error {
+        let message = format!("{} {}", describe_path(path), e);
+        match e {
+            JsonError::Io(e) => Error::read(message, e),
+            JsonError::Invalid { .. } => Error::data(message),
+        }
+    }
+
+    /// an error in the CSV file at `path`, which is a data error unless reading the file failed;
+    /// see [`Error::read`]
+    fn csv(path: &str, e: &csv::Error) -> Error {
+        let message = describe_csv_error(path, e);
+        match e.kind() {
+            csv::ErrorKind::Io(e) => Error::read(message, e),
+            _ => Error::data(message),
         }
     }
 }
 
-fn main() {
-    let opts: Opts = argh::from_env();
+/// Describe the input file at `path` for messages.
+fn describe_path(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
+
+/// Describe an error in the CSV file at `path`, including the line of the offending row if known.
+fn describe_csv_error(path: &str, e: &csv::Error) -> String {
+    let path = describe_path(path);
+    let detail = match e.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+            Some(field) => format!("column {}: {}", field + 1, err.kind()),
+            None => err.kind().to_string(),
+        },
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!(
+            "found {} fields, but the header has {}; is the row truncated?",
+            len, expected_len
+        ),
+        _ => e.to_string(),
+    };
+    match e.position() {
+        Some(pos) => format!("{} line {}: {}", path, pos.line(), detail),
+        None => format!("{}: {}", path, detail),
+    }
+}
 
-    let player_file = std::fs::File::open(opts.players).unwrap();
-    let mut players = read_players(player_file);
+/// Derive a seed from the current time, for sampled runs without an explicit --seed.
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
 
-    let match_file = std::fs::File::open(opts.matches).unwrap();
-    let matches = read_matches(match_file);
+#[derive(Clone, Copy, Debug)]
+/// How placement counts are written
+enum Scale {
+    /// placement counts; expected counts that aren't whole numbers are rounded to `precision`
+    /// decimal places
+    Counts { precision: usize },
+    /// percentages of the total weight of the simulations, rounded to `precision` decimal places
+    Percentages { total: f64, precision: usize },
+}
 
-    let simulations = std::cmp::min(
-        1 << matches.len(),
-        opts.simulation_count.unwrap_or(std::usize::MAX),
-    );
-    for i in 0..simulations {
-        simulate(i, opts.top_ranks, &matches, &mut players);
+impl Scale {
+    /// format the placement `count` on this scale
+    fn format(self, count: f64) -> String {
+        match self {
+            Scale::Counts { precision } => {
+                if count.fract() == 0.0 {
+                    count.to_string()
+                } else {
+                    format!("{:.*}", precision, count)
+                }
+            }
+            Scale::Percentages { total, precision } => {
+                format!("{:.*}", precision, 100.0 * count / total)
+            }
+        }
     }
+}
 
-    let top8 = players
+/// The placements of `player`, in rank order.
+fn sorted_placements(player: &Player) -> Vec<(usize, f64)> {
+    player
+        .placements
         .iter()
-        .filter_map(|p| {
-            if p.1.placements.len() > 0 {
-                Some(p.1)
+        .map(|(&rank, &count)| (rank, count))
+        .collect()
+}
+
+/// The placements of `player` in rank order, as in [`sorted_placements`], or 0 at every one of
+/// the `top_ranks` for a player who never placed, so they still have a row or an entry.
+fn placements_or_zeros(player: &Player, top_ranks: usize) -> Vec<(usize, f64)> {
+    if player.placements.is_empty() {
+        (1..=top_ranks).map(|rank| (rank, 0.0)).collect()
+    } else {
+        sorted_placements(player)
+    }
+}
+
+/// The rank at which `player` most often finished, with its count.
+///
+/// Finishing below the top ranks counts as a finish at rank `top_ranks + 1`, using the part of
+/// `total` not covered by the player's placements. Between ranks with the same count, the better
+/// rank is the most likely one.
+fn likely_finish(player: &Player, top_ranks: usize, total: f64) -> (usize, f64) {
+    let unplaced = total - player.placements.values().sum::<f64>();
+    sorted_placements(player)
+        .into_iter()
+        .chain(std::iter::once((top_ranks + 1, unplaced)))
+        .fold((top_ranks + 1, f64::NEG_INFINITY), |best, (rank, count)| {
+            if count > best.1 {
+                (rank, count)
             } else {
-                None
+                best
             }
         })
-        .collect::<Vec<_>>();
+}
 
-    let output: Box<dyn std::io::Write> = if let Some(file) = opts.output {
-        Box::new(std::fs::File::create(file).unwrap())
-    } else {
-        Box::new(std::io::stdout())
+/// Sort `players` by their most likely finishing position, and then by how likely it is.
+///
+/// Players most likely to finish below the top ranks come last, ordered by how likely they are to
+/// place at all. Players that are otherwise equal are ordered by name.
+fn sort_by_likely_finish(players: &mut [&Player], top_ranks: usize, total: f64) {
+    players.sort_by(|p1, p2| p1.name.cmp(&p2.name));
+    players.sort_by(|p1, p2| {
+        let (rank1, count1) = likely_finish(p1, top_ranks, total);
+        let (rank2, count2) = likely_finish(p2, top_ranks, total);
+        let by_count = if rank1 > top_ranks {
+            count1.partial_cmp(&count2)
+        } else {
+            count2.partial_cmp(&count1)
+        };
+        rank1.cmp(&rank2).then(by_count.unwrap_or(Ordering::Equal))
+    });
+}
+
+/// Sort `players` by how often they finished first, then by how often they placed at all.
+///
+/// Players that are otherwise equal are ordered by name.
+fn sort_by_top_ranks(players: &mut [&Player]) {
+    let first = |p: &Player| p.placements.get(&1).copied().unwrap_or(0.0);
+    let placed = |p: &Player| p.placements.values().sum::<f64>();
+    players.sort_by(|p1, p2| {
+        first(p2)
+            .partial_cmp(&first(p1))
+            .unwrap_or(Ordering::Equal)
+            .then(
+                placed(p2)
+                    .partial_cmp(&placed(p1))
+                    .unwrap_or(Ordering::Equal),
+            )
+            .then_with(|| p1.name.cmp(&p2.name))
+    });
+}
+
+/// Sort `players` by how often they finished in the top `k` ranks, then by how often they finished
+/// first.
+///
+/// Players that are otherwise equal are ordered by name.
+fn sort_by_top_k(players: &mut [&Player], k: usize) {
+    let top_k = |p: &Player| {
+        (1..=k)
+            .filter_map(|rank| p.placements.get(&rank))
+            .sum::<f64>()
     };
-    write_results(&top8, output);
+    let first = |p: &Player| p.placements.get(&1).copied().unwrap_or(0.0);
+    players.sort_by(|p1, p2| {
+        top_k(p2)
+            .partial_cmp(&top_k(p1))
+            .unwrap_or(Ordering::Equal)
+            .then(first(p2).partial_cmp(&first(p1)).unwrap_or(Ordering::Equal))
+            .then_with(|| p1.name.cmp(&p2.name))
+    });
+}
+
+/// Sort `players` by their expected finishing rank, best first.
+///
+/// Players without an expected rank come last, and players that are otherwise equal are ordered
+/// by name.
+fn sort_by_expected_rank(players: &mut [&Player]) {
+    let mean = |p: &Player| p.expected_rank.map_or(f64::INFINITY, |(mean, _)| mean);
+    players.sort_by(|p1, p2| {
+        mean(p1)
+            .partial_cmp(&mean(p2))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| p1.name.cmp(&p2.name))
+    });
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Order in which players are written
+enum PlayerOrder {
+    /// by how often they finished first, and then by how often they placed; see sort_by_top_ranks
+    TopRanks,
+    /// by their chance of a top-k finish, and then by how often they finished first; see
+    /// sort_by_top_k
+    TopK,
+    /// by name
+    Name,
+    /// by their most likely finishing position; see sort_by_likely_finish
+    LikelyFinish,
+    /// by their expected finishing rank; see sort_by_expected_rank
+    ExpectedRank,
+}
+
+impl std::str::FromStr for PlayerOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rank1" => Ok(PlayerOrder::TopRanks),
+            "topk" => Ok(PlayerOrder::TopK),
+            "expected" => Ok(PlayerOrder::ExpectedRank),
+            "name" => Ok(PlayerOrder::Name),
+            _ => Err(format!(
+                "unknown sort order '{}', expected one of: rank1, topk, expected, name",
+                s
+            )),
+        }
+    }
+}
+
+/// A summary statistic written for each player after their placements
+struct SummaryColumn {
+    /// name of the statistic in json and csv output
+    key: &'static str,
+    /// title of the statistic in text and Markdown output
+    title: String,
+    /// unit written after the statistic in text and Markdown output
+    unit: &'static str,
+    /// whether the statistic is a string rather than a number, and needs quoting in json output
+    quoted: bool,
 }
 
-fn simulate(
-    iteration: usize,
+#[derive(Clone, Copy, Debug)]
+/// What is written for each player, and in what order
+struct Report {
+    /// order of the players
+    order: PlayerOrder,
+    /// how placement counts are written
+    scale: Scale,
+    /// number of ranks counted in each simulation
     top_ranks: usize,
-    matches: &Vec<(String, String)>,
-    players: &mut HashMap<String, Player>,
+    /// total weight of the simulations that were run
+    total: f64,
+    /// number of decimal places for summary statistics
+    precision: usize,
+    /// number of top ranks summed up for the chance of a top finish
+    top_k: usize,
+    /// whether to write if each player clinched a top rank, was eliminated, or is still alive
+    status: bool,
+    /// whether to write the expected rank of each player and its standard deviation
+    expected_rank: bool,
+    /// whether to write the variance of the finishing rank of each player
+    volatility: bool,
+    /// whether to write the 95% confidence interval of the chance of a top finish, for sampled
+    /// runs
+    interval: bool,
+    /// whether to write placements in json at full precision, with both their count and
+    /// probability
+    raw: bool,
+    /// whether to color each player in text output by their status, when stdout is a terminal
+    /// that supports colors
+    color: bool,
+}
+
+impl Report {
+    /// the color of a player with `status` in text output, if any
+    fn status_color(&self, status: Option<Status>) -> Option<AnsiColors> {
+        if !self.color || !self.status {
+            return None;
+        }
+        match status? {
+            Status::Clinched => Some(AnsiColors::Green),
+            Status::Eliminated => Some(AnsiColors::Red),
+            Status::Alive => None,
+        }
+    }
+
+    /// the suffix written after each placement
+    fn suffix(&self) -> &'static str {
+        match self.scale {
+            Scale::Counts { .. } => "",
+            Scale::Percentages { .. } => "%",
+        }
+    }
+
+    /// the summary columns written after the placements
+    fn summary_columns(&self) -> Vec<SummaryColumn> {
+        let mut columns = vec![SummaryColumn {
+            key: "top_k_percent",
+            title: format!("Top {}", self.top_k),
+            unit: "%",
+            quoted: false,
+        }];
+        if self.interval {
+            columns.push(SummaryColumn {
+                key: "top_k_low_percent",
+                title: String::from("95% low"),
+                unit: "%",
+                quoted: false,
+            });
+            columns.push(SummaryColumn {
+                key: "top_k_high_percent",
+                title: String::from("95% high"),
+                unit: "%",
+                quoted: false,
+            });
+        }
+        if self.status {
+            columns.push(SummaryColumn {
+                key: "status",
+                title: String::from("Status"),
+                unit: "",
+                quoted: true,
+            });
+        }
+        if self.expected_rank {
+            columns.push(SummaryColumn {
+                key: "expected_rank",
+                title: String::from("Expected rank"),
+                unit: "",
+                quoted: false,
+            });
+            columns.push(SummaryColumn {
+                key: "rank_std_dev",
+                title: String::from("Std dev"),
+                unit: "",
+                quoted: false,
+            });
+        }
+        if self.volatility {
+            columns.push(SummaryColumn {
+                key: "volatility",
+                title: String::from("Volatility"),
+                unit: "",
+                quoted: false,
+            });
+        }
+        columns
+    }
+
+    /// the values of the summary columns for `player`, or None where a value is unknown
+    fn summary(&self, player: &Player) -> Vec<Option<String>> {
+        let format = |value: f64| format!("{:.*}", self.precision, value);
+        let top_k = player.top_k_probability(self.top_k, self.total);
+        let mut values = vec![top_k.map(|p| format(100.0 * p))];
+        if self.interval {
+            let interval = player.top_k_confidence_interval(self.top_k, self.total);
+            values.push(interval.map(|(low, _)| format(100.0 * low)));
+            values.push(interval.map(|(_, high)| format(100.0 * high)));
+        }
+        if self.status {
+            values.push(player.status(self.total).map(|status| status.to_string()));
+        }
+        if self.expected_rank {
+            values.push(player.expected_rank.map(|(mean, _)| format(mean)));
+            values.push(player.expected_rank.map(|(_, std_dev)| format(std_dev)));
+        }
+        if self.volatility {
+            values.push(
+                player
+                    .expected_rank
+                    .map(|(_, std_dev)| format(std_dev * std_dev)),
+            );
+        }
+        values
+    }
+}
+
+/// Sort `players` in the order set in `report`.
+fn sort_players(players: &mut [&Player], report: &Report) {
+    match report.order {
+        PlayerOrder::TopRanks => sort_by_top_ranks(players),
+        PlayerOrder::TopK => sort_by_top_k(players, report.top_k),
+        PlayerOrder::Name => players.sort_by(|p1, p2| p1.name.cmp(&p2.name)),
+        PlayerOrder::LikelyFinish => sort_by_likely_finish(players, report.top_ranks, report.total),
+        PlayerOrder::ExpectedRank => sort_by_expected_rank(players),
+    }
+}
+
+/// Write the placements of `players` in the given format, sorted as set in `report`.
+///
+/// Players are written one at a time as they are formatted, so that the output for a large field
+/// is never held in memory all at once.
+fn write_results(
+    mut players: Vec<&Player>,
+    report: &Report,
+    format: OutputFormat,
+    w: Box<dyn std::io::Write>,
 ) {
-    let mut players_copy = players.clone();
-    for (matchnum, matchplayers) in matches.iter().enumerate() {
-        let (winner, loser) = if iteration & (1 << matchnum) == 0 {
-            (&matchplayers.0, &matchplayers.1)
+    sort_players(&mut players, report);
+    match format {
+        OutputFormat::Text => write_text(&players, report, w),
+        OutputFormat::Json => write_json(&players, report, w),
+        OutputFormat::Csv => write_csv(&players, report, w),
+        OutputFormat::Markdown => write_markdown(&players, report, w),
+        OutputFormat::RankMatrix => write_rank_matrix(&players, report, w),
+        OutputFormat::Summary => write_summary(&players, report, w),
+    }
+}
+
+/// Write the placements of `players` as text, in the given order.
+///
+/// Players without any placements are listed as having no top finishes. Summary statistics follow
+/// the placements. With `report.color`, clinched players are green and eliminated ones red.
+fn write_text(players: &[&Player], report: &Report, mut w: Box<dyn std::io::Write>) {
+    writeln!(w, "final players:").unwrap();
+    let columns = report.summary_columns();
+    for player in players {
+        let placements = if player.placements.is_empty() {
+            format!("0 top-{} finishes", report.top_ranks)
         } else {
-            (&matchplayers.1, &matchplayers.0)
+            let placements = sorted_placements(player)
+                .into_iter()
+                .map(|(rank, count)| {
+                    format!(
+                        "{}: {}{}",
+                        rank,
+                        report.scale.format(count),
+                        report.suffix()
+                    )
+                })
+                .collect::<Vec<_>>();
+            format!("{{{}}}", placements.join(", "))
         };
+        let summary = columns
+            .iter()
+            .zip(report.summary(player))
+            .filter_map(|(column, value)| {
+                let title = column.title.to_lowercase();
+                Some(format!("{}: {}{}", title, value?, column.unit))
+            })
+            .collect::<Vec<_>>();
+        let line = if summary.is_empty() {
+            format!("{}: {}", player.name, placements)
+        } else {
+            format!("{}: {} ({})", player.name, placements, summary.join(", "))
+        };
+        match report.status_color(player.status(report.total)) {
+            Some(color) => {
+                let line = line.if_supports_color(Stream::Stdout, |line| line.color(color));
+                writeln!(w, "  {}", line).unwrap()
+            }
+            None => writeln!(w, "  {}", line).unwrap(),
+        }
+    }
+    w.flush().unwrap();
+}
 
-        // XXX: use information about number of opponents instead of hardcoding to 4
-        let mut opp_wins = Vec::with_capacity(4);
-        players_copy.entry(winner.to_string()).and_modify(|e| {
-            e.add_win();
-            opp_wins.extend(e.opponents.iter().cloned());
-        });
-        for opponent in opp_wins {
-            players_copy
-                .entry(opponent)
-                .and_modify(Player::add_opponent_win);
+/// A placement in JSON output with `report.raw`, at full precision
+#[derive(Serialize)]
+struct RawPlacement {
+    count: f64,
+    probability: f64,
+}
+
+/// A number in JSON output, written with the digits it was formatted with, or null if `text` is
+/// not a valid JSON number
+fn json_number(text: String) -> Option<Box<RawValue>> {
+    RawValue::from_string(text).ok()
+}
+
+/// A player as an object of JSON output; see [`write_json`]
+struct JsonPlayer<'a> {
+    player: &'a Player,
+    report: &'a Report,
+    columns: &'a [SummaryColumn],
+}
+
+impl Serialize for JsonPlayer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (player, report) = (self.player, self.report);
+        let mut object = serializer.serialize_map(None)?;
+        object.serialize_entry("name", &player.name)?;
+        object.serialize_entry("placements", &JsonPlacements { player, report })?;
+        for (column, value) in self.columns.iter().zip(report.summary(player)) {
+            match value {
+                Some(value) if column.quoted => object.serialize_entry(column.key, &value)?,
+                value => object.serialize_entry(column.key, &value.and_then(json_number))?,
+            }
+        }
+        object.end()
+    }
+}
+
+/// The placements of a player as an object of JSON output, keyed by rank
+struct JsonPlacements<'a> {
+    player: &'a Player,
+    report: &'a Report,
+}
+
+impl Serialize for JsonPlacements<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let report = self.report;
+        let mut placements = serializer.serialize_map(None)?;
+        for (rank, count) in placements_or_zeros(self.player, report.top_ranks) {
+            if report.raw {
+                let probability = count / report.total;
+                placements.serialize_entry(&rank, &RawPlacement { count, probability })?;
+            } else {
+                placements.serialize_entry(&rank, &json_number(report.scale.format(count)))?;
+            }
+        }
+        placements.end()
+    }
+}
+
+/// Write `players` as a JSON array, in the given order, with one player per line.
+///
+/// Each player is an object with the player's `name` and their `placements`, an object keyed by
+/// rank in ascending order (0 at every rank for a player who never placed), followed by any
+/// summary statistics. Each placement is a count or a percentage, depending on the scale of
+/// `report`. With `report.raw`, each placement is instead an object with the unrounded `count` and
+/// its `probability` out of the total weight of the simulations, written with as many digits as it
+/// takes to read the same f64 back.
+fn write_json(players: &[&Player], report: &Report, mut w: Box<dyn std::io::Write>) {
+    let columns = report.summary_columns();
+    writeln!(w, "[").unwrap();
+    for (i, &player) in players.iter().enumerate() {
+        write!(w, "  ").unwrap();
+        let player = JsonPlayer {
+            player,
+            report,
+            columns: &columns,
+        };
+        serde_json::to_writer(&mut w, &player).unwrap();
+        let separator = if i + 1 == players.len() { "" } else { "," };
+        writeln!(w, "{}", separator).unwrap();
+    }
+    writeln!(w, "]").unwrap();
+    w.flush().unwrap();
+}
+
+/// Write `players` as CSV with `name`, `rank`, and `count` columns, in the given order of players
+/// and then by rank.
+///
+/// If placements are written as percentages, the `count` column is a `percent` instead. Summary
+/// statistics are written in additional columns, repeated on each row of the player. A player who
+/// never placed has a row with 0 for every rank.
+fn write_csv(players: &[&Player], report: &Report, w: Box<dyn std::io::Write>) {
+    let mut writer = csv::Writer::from_writer(w);
+    let value_column = match report.scale {
+        Scale::Counts { .. } => "count",
+        Scale::Percentages { .. } => "percent",
+    };
+    let columns = report.summary_columns();
+    let header = ["name", "rank", value_column];
+    let header = header
+        .iter()
+        .copied()
+        .chain(columns.iter().map(|column| column.key));
+    writer.write_record(header).unwrap();
+    for player in players {
+        let summary = report
+            .summary(player)
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect::<Vec<_>>();
+        for (rank, count) in placements_or_zeros(player, report.top_ranks) {
+            let row = [
+                player.name.clone(),
+                rank.to_string(),
+                report.scale.format(count),
+            ];
+            writer.write_record(row.iter().chain(&summary)).unwrap();
         }
+    }
+    writer.flush().unwrap();
+}
 
-        let mut opp_losses = Vec::with_capacity(4);
-        players_copy.entry(loser.to_string()).and_modify(|e| {
-            e.add_loss();
-            opp_losses.extend(e.opponents.iter().cloned());
+/// Write how often each of `players` finished above each other as a CSV matrix, sorted as set in
+/// `report`.
+///
+/// The first column has the name of the player of each row, and the header has the name of the
+/// player of each other column. Each cell is the percentage of the simulations in which the player
+/// of the row finished above the player of the column, and the diagonal is empty.
+fn write_matrix(
+    mut players: Vec<&Player>,
+    report: &Report,
+    results: &Results,
+    w: Box<dyn std::io::Write>,
+) {
+    sort_players(&mut players, report);
+    let mut writer = csv::Writer::from_writer(w);
+    let header = std::iter::once("player").chain(players.iter().map(|p| p.name.as_str()));
+    writer.write_record(header).unwrap();
+    for player in &players {
+        let cells = players.iter().map(|other| {
+            if player.name == other.name {
+                return String::new();
+            }
+            match results.finishes_above(&player.name, &other.name) {
+                Some(p) => format!("{:.*}", report.precision, 100.0 * p),
+                None => String::new(),
+            }
         });
-        for opponent in opp_losses {
-            players_copy
-                .entry(opponent)
-                .and_modify(Player::add_opponent_loss);
-        }
-    }
-    let mut ranking: Vec<_> = players_copy.values().collect();
-    // Reverse the sort to get highest win total first
-    ranking.sort_by(|p1, p2| rank_players(p1, p2).reverse());
-    for (rank, player) in ranking.iter().enumerate().take(top_ranks) {
-        players.entry(player.name.clone()).and_modify(|e| {
-            e.add_placement(rank + 1);
+        let row = std::iter::once(player.name.clone()).chain(cells);
+        writer.write_record(row).unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+/// Write `players` as a dense CSV matrix of their placements, in the given order.
+///
+/// The header has a `name` column and a column for each rank up to the number of top ranks, and
+/// each row has a player's placement at every rank, with 0 for ranks they never finished at. Each
+/// cell is a count or a percentage, depending on the scale of `report`.
+fn write_rank_matrix(players: &[&Player], report: &Report, w: Box<dyn std::io::Write>) {
+    let mut writer = csv::Writer::from_writer(w);
+    let header = std::iter::once(String::from("name"))
+        .chain((1..=report.top_ranks).map(|rank| rank.to_string()));
+    writer.write_record(header).unwrap();
+    for player in players {
+        let cells = (1..=report.top_ranks).map(|rank| {
+            let count = player.placements.get(&rank).copied().unwrap_or(0.0);
+            report.scale.format(count)
         });
+        let row = std::iter::once(player.name.clone()).chain(cells);
+        writer.write_record(row).unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+/// Width of the longest bar of a histogram, in characters
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Draw a histogram of the finishing ranks of each of `players`, sorted as set in `report`.
+///
+/// Each rank up to the number of top ranks has a row with a bar and the percentage of simulations
+/// in which the player finished there, and a last row counts the simulations in which they
+/// finished outside the top ranks. The most likely row of each player gets a bar of
+/// [`HISTOGRAM_WIDTH`] characters, and the others a bar in proportion.
+fn write_histogram(mut players: Vec<&Player>, report: &Report, mut w: Box<dyn std::io::Write>) {
+    sort_players(&mut players, report);
+    let label_width = report.top_ranks.to_string().len().max(3);
+    for player in players {
+        let placed = (1..=report.top_ranks)
+            .map(|rank| player.placements.get(&rank).copied().unwrap_or(0.0))
+            .collect::<Vec<_>>();
+        let out = (report.total - placed.iter().sum::<f64>()).max(0.0);
+        let rows = placed
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| ((i + 1).to_string(), count))
+            .chain(std::iter::once((String::from("out"), out)))
+            .collect::<Vec<_>>();
+        let most = rows.iter().map(|&(_, count)| count).fold(0.0, f64::max);
+        writeln!(w, "{}:", player.name).unwrap();
+        for (label, count) in rows {
+            let bar = if most > 0.0 {
+                (count / most * HISTOGRAM_WIDTH as f64).round() as usize
+            } else {
+                0
+            };
+            let percent = if report.total > 0.0 {
+                100.0 * count / report.total
+            } else {
+                0.0
+            };
+            writeln!(
+                w,
+                "  {:>label_width$} | {:<bar_width$} {:.*}%",
+                label,
+                "#".repeat(bar),
+                report.precision,
+                percent,
+                label_width = label_width,
+                bar_width = HISTOGRAM_WIDTH,
+            )
+            .unwrap();
+        }
+    }
+    w.flush().unwrap();
+}
+
+/// Write the summary statistics of `players` as CSV, in the given order.
+///
+/// Each row has a player's name and a column for each summary statistic, as in csv output, but not
+/// their placements at each rank, so each player has a single row.
+fn write_summary(players: &[&Player], report: &Report, w: Box<dyn std::io::Write>) {
+    let mut writer = csv::Writer::from_writer(w);
+    let columns = report.summary_columns();
+    let header = std::iter::once("name").chain(columns.iter().map(|column| column.key));
+    writer.write_record(header).unwrap();
+    for player in players {
+        let summary = report
+            .summary(player)
+            .into_iter()
+            .map(Option::unwrap_or_default);
+        let row = std::iter::once(player.name.clone()).chain(summary);
+        writer.write_record(row).unwrap();
     }
-    if iteration % 10000 == 0 {
-        println!("iteration: {}", iteration);
+    writer.flush().unwrap();
+}
+
+/// Write `players` as a Markdown table, in the given order.
+///
+/// The table has a `Player` column, a column for each rank up to the number of top ranks, and a
+/// column for each summary statistic. Each rank cell is a count or a percentage, depending on the
+/// scale of `report`. Columns are padded so the table also reads well as plain text.
+///
+/// The column widths need every row, so rows are formatted twice: once to measure them, and again
+/// to write them, rather than keeping all of them.
+fn write_markdown(players: &[&Player], report: &Report, mut w: Box<dyn std::io::Write>) {
+    let columns = report.summary_columns();
+    let header = std::iter::once(String::from("Player"))
+        .chain((1..=report.top_ranks).map(|rank| rank.to_string()))
+        .chain(columns.iter().map(|column| column.title.clone()))
+        .collect::<Vec<_>>();
+    let format_row =
+        |player: &Player| {
+            std::iter::once(player.name.replace('|', "\\|"))
+                .chain((1..=report.top_ranks).map(|rank| {
+                    let count = player.placements.get(&rank).copied().unwrap_or(0.0);
+                    format!("{}{}", report.scale.format(count), report.suffix())
+                }))
+                .chain(columns.iter().zip(report.summary(player)).map(
+                    |(column, value)| match value {
+                        Some(value) => format!("{}{}", value, column.unit),
+                        None => String::new(),
+                    },
+                ))
+                .collect::<Vec<_>>()
+        };
+    let mut widths = header
+        .iter()
+        .map(|cell| cell.chars().count().max(3))
+        .collect::<Vec<_>>();
+    for player in players {
+        for (width, cell) in widths.iter_mut().zip(format_row(player)) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let write_row = |w: &mut Box<dyn std::io::Write>, row: &[String]| {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(column, (cell, &width))| {
+                if column == 0 {
+                    format!(" {:<width$} ", cell, width = width)
+                } else {
+                    format!(" {:>width$} ", cell, width = width)
+                }
+            })
+            .collect::<Vec<_>>();
+        writeln!(w, "|{}|", cells.join("|")).unwrap();
+    };
+    write_row(&mut w, &header);
+    let separator = widths
+        .iter()
+        .enumerate()
+        .map(|(column, &width)| {
+            if column == 0 {
+                format!(" {} ", "-".repeat(width))
+            } else {
+                format!(" {}: ", "-".repeat(width - 1))
+            }
+        })
+        .collect::<Vec<_>>();
+    writeln!(w, "|{}|", separator.join("|")).unwrap();
+    for player in players {
+        write_row(&mut w, &format_row(player));
+    }
+    w.flush().unwrap();
+}
+
+/// The version of the build, with the git commit it was built from
+fn version() -> String {
+    format!(
+        "ccls {} (commit {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("CCLS_GIT_COMMIT")
+    )
+}
+
+/// Target of the log records of [`note`]
+const NOTE_TARGET: &str = "note";
+
+/// Write diagnostics to stderr with env_logger, filtered by the RUST_LOG environment variable.
+///
+/// Without RUST_LOG, warnings and errors are written. Notes are written whatever RUST_LOG says,
+/// unless the run is `quiet`, which only writes errors. Errors that end the run are written by
+/// `main` itself, so they are never filtered out.
+fn init_logger(quiet: bool) {
+    use std::io::Write;
+
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(LevelFilter::Warn)
+        .parse_default_env()
+        .filter_module(NOTE_TARGET, LevelFilter::Info)
+        .format(|buf, record| {
+            let prefix = match record.level() {
+                _ if record.target() == NOTE_TARGET => "note",
+                Level::Error => "error",
+                Level::Warn => "warning",
+                Level::Info => "info",
+                Level::Debug => "debug",
+                Level::Trace => "trace",
+            };
+            writeln!(buf, "{}: {}", prefix, record.args())
+        });
+    if quiet {
+        builder.filter_level(LevelFilter::Error);
+    }
+    builder.init();
+    if quiet {
+        // RUST_LOG may still enable more for some modules, which a quiet run leaves out.
+        log::set_max_level(LevelFilter::Error);
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e.message);
+        std::process::exit(e.kind.exit_code());
+    }
+}
+
+/// Parse the command line as `argh::from_env` does, but write usage errors to stderr and exit with
+/// the status for usage errors if it is invalid.
+fn parse_args() -> Opts {
+    let args = std::env::args().collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    Opts::from_args(&args[..1], &args[1..]).unwrap_or_else(|early_exit| {
+        // --help is output the user asked for, but an invalid command line is an error.
+        if early_exit.status.is_ok() {
+            println!("{}", early_exit.output);
+            std::process::exit(0);
+        }
+        eprintln!("{}", early_exit.output);
+        std::process::exit(ErrorKind::Usage.exit_code())
+    })
+}
+
+/// Run the command given on the command line.
+fn run() -> Result<(), Error> {
+    let mut opts = parse_args();
+    if opts.version {
+        println!("{}", version());
+        return Ok(());
+    }
+    init_logger(opts.quiet);
+    let command = opts
+        .command
+        .take()
+        .unwrap_or_else(|| Command::Simulate(SimulateOpts::from_args(&["simulate"], &[]).unwrap()));
+
+    match &command {
+        Command::Simulate(sim) => check_simulate_opts(sim)?,
+        Command::DryRun(dry) => check_run_size(dry.simulation_count, dry.sample, dry.target_error)?,
+        Command::Analyze(_) => {}
+    }
+
+    if !(opts.prior_momentum.is_finite() && opts.prior_momentum >= 0.0) {
+        return Err(Error::usage(
+            "--prior-momentum must be a number of at least 0",
+        ));
+    }
+
+    let tiebreakers = match (opts.tiebreakers, opts.tiebreaker) {
+        (Some(_), Some(_)) => {
+            return Err(Error::usage(
+                "--tiebreaker cannot be used with --tiebreakers, which it is an old name for",
+            ))
+        }
+        (None, Some(tiebreakers)) => {
+            warn!("--tiebreaker is deprecated; use --tiebreakers");
+            tiebreakers
+        }
+        (tiebreakers, None) => tiebreakers.unwrap_or_default(),
+    };
+
+    for (option, points) in [
+        ("--win-points", Some(opts.win_points)),
+        ("--draw-points", Some(opts.draw_points)),
+        ("--loss-points", Some(opts.loss_points)),
+        ("--bye-points", opts.bye_points),
+    ] {
+        if points.is_some_and(|points| !(points.is_finite() && points >= 0.0)) {
+            return Err(Error::usage(format!(
+                "{} must be a number of at least 0",
+                option
+            )));
+        }
+    }
+
+    if !(0.0..=1.0).contains(&opts.winrate_floor) {
+        return Err(Error::usage("--winrate-floor must be between 0 and 1"));
+    }
+
+    if opts.swiss && (opts.matches.is_some() || opts.round_robin.is_some()) {
+        return Err(Error::usage(
+            "--swiss cannot be used with --matches or --round-robin",
+        ));
+    }
+
+    if opts.matches.is_none() && opts.round_robin.is_none() && !opts.swiss {
+        return Err(Error::usage(
+            "--matches is required unless --round-robin or --swiss is given",
+        ));
+    }
+
+    if opts.players.is_empty() {
+        return Err(Error::usage("--players is required"));
+    }
+
+    match (opts.top_ranks, opts.top_percent) {
+        (Some(_), Some(_)) => {
+            return Err(Error::usage(
+                "--top-ranks and --top-percent cannot be used together",
+            ));
+        }
+        (None, None) => return Err(Error::usage("--top-ranks or --top-percent is required")),
+        (None, Some(percent)) if !(percent > 0.0 && percent <= 100.0) => {
+            return Err(Error::usage(
+                "--top-percent must be greater than 0 and at most 100",
+            ));
+        }
+        _ => {}
+    }
+
+    let stdin_inputs = opts
+        .players
+        .iter()
+        .chain(&opts.matches)
+        .filter(|path| *path == STDIN)
+        .count();
+    if stdin_inputs > 1 {
+        return Err(Error::usage(
+            "only one of the --players and --matches files can be read from stdin",
+        ));
+    }
+
+    let mut players = HashMap::new();
+    for path in &opts.players {
+        let player_file = open_input(path, "players")?;
+        let file_players = match opts.input_format {
+            InputFormat::Csv => read_players(player_file, opts.name_prefix_len, opts.delimiter.0)
+                .map_err(|e| Error::csv(path, &e))?,
+            InputFormat::Json => read_players_json(player_file, opts.name_prefix_len)
+                .map_err(|e| Error::json(path, &e))?,
+        };
+        if file_players.is_empty() {
+            return Err(Error::data(format!(
+                "no players loaded from {}",
+                describe_path(path)
+            )));
+        }
+        info!(
+            "loaded {} players from {}",
+            file_players.len(),
+            describe_path(path)
+        );
+        merge_players(&mut players, file_players, opts.on_duplicate, path)?;
+    }
+    let player_source = match opts.players.as_slice() {
+        [path] => describe_path(path),
+        _ => "the players files",
+    };
+
+    let name_case = if opts.ignore_case {
+        let name_case = fold_player_case(&mut players).map_err(|collision| {
+            Error::data(format!(
+                "players '{}' and '{}' in {} only differ in case, so --ignore-case can't tell \
+                 them apart",
+                collision.first, collision.second, player_source
+            ))
+        })?;
+        Some(name_case)
+    } else {
+        None
+    };
+
+    if opts.validate {
+        for mismatch in check_opponent_records(&players) {
+            warn!(
+                "{} has opp_wins {} and opp_losses {}, but their opponents have {} wins and {} \
+                 losses against other players",
+                mismatch.name,
+                mismatch.opp_wins,
+                mismatch.opp_losses,
+                mismatch.expected_wins,
+                mismatch.expected_losses
+            );
+        }
+    }
+
+    if players.contains_key(BYE) {
+        return Err(Error::data(format!(
+            "{} has a player named {}, which is reserved for byes",
+            player_source, BYE
+        )));
+    }
+
+    if tiebreakers.contains(Tiebreaker::OpponentGameWinrate) {
+        // Without game records the tiebreaker would only see the simulated games, so it is an
+        // error rather than a silent fallback to match results.
+        let (columns, has_games) = if opts.trust_csv_opp {
+            let has_games = players
+                .values()
+                .any(|p| p.opp_game_wins > 0 || p.opp_game_losses > 0);
+            ("opp_game_wins or opp_game_losses", has_games)
+        } else {
+            let has_games = players
+                .values()
+                .any(|p| p.game_wins > 0 || p.game_losses > 0);
+            ("game_wins or game_losses", has_games)
+        };
+        if !has_games {
+            return Err(Error::data(format!(
+                "the oppgwr tiebreaker needs game records, but {} has no {}",
+                player_source, columns
+            )));
+        }
+    }
+
+    if let Some(percent) = opts.top_percent {
+        // Multiply before dividing so that whole percentages of the field don't round up.
+        let top_ranks = (percent * players.len() as f64 / 100.0).ceil() as usize;
+        let top_ranks = top_ranks.clamp(1, players.len());
+        info!(
+            "--top-percent {}: computing the top {} of {} players",
+            percent,
+            top_ranks,
+            players.len()
+        );
+        opts.top_ranks = Some(top_ranks);
+    }
+    if opts.top_ranks() == 0 || opts.top_ranks() > players.len() {
+        return Err(Error::usage(format!(
+            "--top-ranks must be between 1 and the number of players in {} ({})",
+            player_source,
+            players.len()
+        )));
+    }
+
+    if let Command::Simulate(sim) = &command {
+        if sim.top_k.is_some_and(|top_k| top_k > opts.top_ranks()) {
+            return Err(Error::usage("--top-k cannot be larger than the top ranks"));
+        }
+    }
+
+    if let Command::Simulate(sim) = &command {
+        for name in sim
+            .player
+            .iter()
+            .filter(|name| !players.contains_key(*name))
+        {
+            warn!("player '{}' for --player is not in {}", name, player_source);
+        }
+    }
+
+    let mut matches = match &opts.matches {
+        Some(path) => {
+            let match_file = open_input(path, "matches")?;
+            let matches = match opts.input_format {
+                InputFormat::Csv => {
+                    read_matches(match_file, opts.name_prefix_len, opts.delimiter.0)
+                        .map_err(|e| Error::csv(path, &e))?
+                }
+                InputFormat::Json => read_matches_json(match_file, opts.name_prefix_len)
+                    .map_err(|e| Error::json(path, &e))?,
+            };
+            info!(
+                "loaded {} matches from {}",
+                matches.len(),
+                describe_path(path)
+            );
+            matches
+        }
+        None => Vec::new(),
+    };
+    let match_source = match &opts.matches {
+        Some(path) => describe_path(path),
+        None if opts.swiss => "--swiss",
+        None => "--round-robin",
+    };
+
+    if let Some(group) = &opts.round_robin {
+        let mut group = read_group(group)?;
+        if let Some(name_case) = &name_case {
+            group = group
+                .into_iter()
+                .map(|name| name_case.spelling(name))
+                .collect();
+        }
+        if let Some(name) = group.iter().find(|name| !players.contains_key(*name)) {
+            return Err(Error::data(format!(
+                "player '{}' for --round-robin is not in {}",
+                name, player_source
+            )));
+        }
+        let added = add_round_robin(&mut matches, &group);
+        info!(
+            "added {} round-robin matches among {} players",
+            added,
+            group.len()
+        );
+    }
+
+    if opts.swiss {
+        let pairs = swiss_pairings(&players, opts.point_values());
+        note!(
+            "swiss pairings: {}",
+            pairs
+                .iter()
+                .map(|(player1, player2)| format!("{} vs {}", player1, player2))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        add_pairings(&mut matches, &pairs);
+    }
+    if let Some(name_case) = &name_case {
+        matches = fold_match_case(matches, name_case);
+    }
+    for match_ in &matches {
+        let others = match_
+            .others
+            .iter()
+            .map(|name| format!(" vs {}", name))
+            .collect::<String>();
+        debug!(
+            "match {} vs {}{}: p1_win {}, winner {}, best of {}",
+            match_.player1,
+            match_.player2,
+            others,
+            match_.p1_win.map_or("unset".to_string(), |p| p.to_string()),
+            match_.winner.as_deref().unwrap_or("none"),
+            match_.best_of.unwrap_or(1)
+        );
+    }
+
+    // Without matches, the current standings are ranked once, which is still a valid run.
+    if matches.is_empty() {
+        note!(
+            "no matches to simulate in {}; ranking the current standings",
+            match_source
+        );
+    }
+
+    let unknown = unknown_players(&players, &matches);
+    if !unknown.is_empty() {
+        let message = format!(
+            "{} names players that are not in {}: {}",
+            match_source,
+            player_source,
+            unknown
+                .iter()
+                .map(|name| format!("\"{}\"", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if opts.strict {
+            return Err(Error::data(message));
+        }
+        warn!("{}", message);
+    }
+
+    if opts.validate {
+        for mismatch in check_opponent_lists(&players, &matches) {
+            if !mismatch.unlisted.is_empty() {
+                warn!(
+                    "{} is listed as an opponent by {}, but doesn't list them",
+                    mismatch.name,
+                    mismatch.unlisted.join(", ")
+                );
+            }
+            if !mismatch.rematches.is_empty() {
+                warn!(
+                    "{} plays {} in {}, but already lists them as opponents in {}",
+                    mismatch.name,
+                    mismatch.rematches.join(", "),
+                    match_source,
+                    player_source
+                );
+            }
+        }
+    }
+
+    let player_count = players.len();
+    let loaded_matches = matches.len();
+    let mut builder = Simulator::builder(players, matches)
+        .top_ranks(opts.top_ranks())
+        .rank_by(opts.rank_by)
+        .tiebreakers(tiebreakers)
+        .trust_csv_opp(opts.trust_csv_opp)
+        .winrate_floor(opts.winrate_floor)
+        .pooled_opp_winrate(opts.pooled_opp_winrate)
+        .split_ties(opts.split_ties)
+        .draws(opts.draws)
+        .prior_momentum(opts.prior_momentum)
+        .points(opts.point_values());
+    if let Some(threads) = opts.threads {
+        builder = builder.threads(threads);
+    }
+
+    match &command {
+        Command::Simulate(sim) => simulate(builder, &opts, sim),
+        Command::DryRun(dry) => dry_run(&builder.build(), player_count, loaded_matches, &opts, dry),
+        Command::Analyze(analyze) => analyze_player(&builder.build(), analyze, &opts),
+    }
+}
+
+/// Check the options of the simulate command that don't depend on the input files.
+fn check_simulate_opts(sim: &SimulateOpts) -> Result<(), Error> {
+    check_run_size(sim.simulation_count, sim.sample, sim.target_error)?;
+
+    if sim
+        .max_runtime
+        .is_some_and(|seconds| !seconds.is_finite() || seconds <= 0.0)
+    {
+        return Err(Error::usage(
+            "--max-runtime must be a number of seconds greater than 0",
+        ));
+    }
+
+    if sim.max_runtime.is_some() && (sim.inspect.is_some() || sim.baseline) {
+        return Err(Error::usage(
+            "--max-runtime cannot be used with --inspect or --baseline",
+        ));
+    }
+
+    if sim.target_error.is_some() && (sim.checkpoint.is_some() || sim.resume.is_some()) {
+        return Err(Error::usage(
+            "--target-error cannot be used with --checkpoint or --resume",
+        ));
+    }
+
+    if sim.matrix && !matches!(sim.format, OutputFormat::Text | OutputFormat::Csv) {
+        return Err(Error::usage("--matrix is always written as csv"));
+    }
+
+    if sim.inspect.is_some()
+        && (sim.checkpoint.is_some()
+            || sim.resume.is_some()
+            || sim.target_error.is_some()
+            || sim.matrix)
+    {
+        return Err(Error::usage(
+            "--inspect cannot be used with --checkpoint, --resume, --target-error, or --matrix",
+        ));
+    }
+
+    if sim.baseline
+        && (sim.simulation_count.is_some()
+            || sim.sample.is_some()
+            || sim.target_error.is_some()
+            || sim.checkpoint.is_some()
+            || sim.resume.is_some()
+            || sim.matrix
+            || sim.inspect.is_some())
+    {
+        return Err(Error::usage(
+            "--baseline cannot be used with -n, --sample, --target-error, --checkpoint, --resume, \
+             --matrix, or --inspect",
+        ));
+    }
+
+    if (sim.summary_output.is_some() || sim.detail_output.is_some() || sim.distinct_standings)
+        && (sim.inspect.is_some() || sim.baseline)
+    {
+        return Err(Error::usage(
+            "--summary-output, --detail-output, and --distinct-standings cannot be used with \
+             --inspect or --baseline",
+        ));
+    }
+
+    if let (Some(simulation), Some(samples)) = (sim.inspect, sim.sample) {
+        if sim.seed.is_none() {
+            return Err(Error::usage(
+                "--inspect with --sample needs the --seed of the run to replay",
+            ));
+        }
+        if simulation >= samples {
+            return Err(Error::usage(format!(
+                "--inspect {} is not one of the {} samples",
+                simulation, samples
+            )));
+        }
+    }
+
+    if sim.histogram {
+        if sim.player.is_empty() {
+            return Err(Error::usage("--histogram needs a --player to draw"));
+        }
+        if sim.format != OutputFormat::Text || sim.matrix {
+            return Err(Error::usage(
+                "--histogram is always drawn as text, and can't be used with --matrix",
+            ));
+        }
+    }
+
+    if sim.raw && sim.format != OutputFormat::Json {
+        return Err(Error::usage("--raw only applies to --format json"));
+    }
+
+    if sim.matrix && (sim.checkpoint.is_some() || sim.resume.is_some()) {
+        return Err(Error::usage(
+            "--matrix cannot be used with --checkpoint or --resume",
+        ));
+    }
+
+    if sim.distinct_standings && (sim.checkpoint.is_some() || sim.resume.is_some()) {
+        return Err(Error::usage(
+            "--distinct-standings cannot be used with --checkpoint or --resume",
+        ));
+    }
+    Ok(())
+}
+
+/// Check the options that set the number of simulations.
+fn check_run_size(
+    simulation_count: Option<usize>,
+    sample: Option<usize>,
+    target_error: Option<f64>,
+) -> Result<(), Error> {
+    if simulation_count == Some(0) {
+        return Err(Error::usage("--simulation-count must be at least 1"));
+    }
+
+    if sample == Some(0) {
+        return Err(Error::usage("--sample must be at least 1"));
+    }
+
+    if target_error.is_some_and(|error| error.is_nan() || error <= 0.0) {
+        return Err(Error::usage("--target-error must be greater than 0"));
+    }
+
+    if simulation_count.is_some() && (sample.is_some() || target_error.is_some()) {
+        return Err(Error::usage(
+            "--sample and --simulation-count cannot be used together",
+        ));
+    }
+    Ok(())
+}
+
+/// Run the simulate command with the simulator configured in `builder`, and write the results.
+fn simulate(mut builder: SimulatorBuilder, opts: &Opts, sim: &SimulateOpts) -> Result<(), Error> {
+    builder = builder
+        .pairwise(sim.matrix)
+        .distinct_standings(sim.distinct_standings);
+    let progress_interval = if opts.quiet { 0 } else { sim.progress_interval };
+    let progress = Arc::new(Progress::new(
+        progress_interval,
+        std::io::stderr().is_terminal(),
+    ));
+    if progress_interval > 0 {
+        let progress = Arc::clone(&progress);
+        builder = builder.progress(move |_| progress.tick());
+    }
+    let simulator = builder.build();
+
+    if sim.baseline {
+        let mut w = open_output(opts)?;
+        writeln!(w, "current standings:").unwrap();
+        write_standings(&simulator.baseline(), opts.top_ranks(), "  ", &mut w);
+        w.flush().unwrap();
+        return Ok(());
+    }
+    if let Some(simulation) = sim.inspect {
+        return inspect(&simulator, simulation, opts, sim);
+    }
+
+    // A runtime too long to represent is no limit at all.
+    let deadline = sim
+        .max_runtime
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .and_then(|runtime| Instant::now().checked_add(runtime));
+
+    let resumed = match &sim.resume {
+        Some(path) => {
+            let file = open_input(path, "checkpoint")?;
+            Some(Checkpoint::read(file).map_err(|e| Error::csv(path, &e))?)
+        }
+        None => None,
+    };
+
+    if let Some(samples) = sample_count(sim.sample, sim.target_error) {
+        let seed = sim
+            .seed
+            .or_else(|| resumed.as_ref().and_then(Checkpoint::seed))
+            .unwrap_or_else(|| {
+                let seed = random_seed();
+                note!("sampling with seed {}", seed);
+                seed
+            });
+        if let Some(target) = sim.target_error {
+            let results = run_to_target(
+                &simulator, samples, seed, target, deadline, &progress, opts, sim,
+            );
+            return write_output(&results, true, opts, sim);
+        }
+        let checkpoint = match resumed {
+            Some(checkpoint) => check_resumed(checkpoint, samples, Some(seed), sim)?,
+            None => simulator.checkpoint_sampled(samples, seed),
+        };
+        let (results, _) = run_checkpointed(&simulator, checkpoint, deadline, &progress, sim)?;
+        return write_output(&results, true, opts, sim);
+    }
+
+    let match_count = simulator.match_count();
+    let simulations = match (simulator.simulation_space(), sim.simulation_count) {
+        (Some(space), count) => std::cmp::min(space, count.unwrap_or(usize::MAX)),
+        (None, Some(count)) => {
+            note!(
+                "{} matches give too many possible outcomes to simulate exhaustively; \
+                 running the first {} simulations",
+                match_count,
+                count
+            );
+            count
+        }
+        (None, None) => {
+            return Err(Error::usage(format!(
+                "{} matches give too many possible outcomes to simulate exhaustively; use \
+                 --sample to run random simulations instead",
+                match_count
+            )));
+        }
+    };
+    // A whole exhaustive run can walk the outcomes in Gray code order, which is faster, but can't
+    // be split into checkpoints or stopped early.
+    if resumed.is_none() && sim.checkpoint.is_none() && deadline.is_none() {
+        if let Some(space) = simulator
+            .simulation_space()
+            .filter(|&space| space == simulations)
+        {
+            progress.start(space);
+            let results = simulator.run_exhaustive().unwrap();
+            progress.finish();
+            return write_output(&results, sim.percent, opts, sim);
+        }
+    }
+    let checkpoint = match resumed {
+        Some(checkpoint) => check_resumed(checkpoint, simulations, None, sim)?,
+        None => simulator.checkpoint_first(simulations),
+    };
+    let (results, stopped) = run_checkpointed(&simulator, checkpoint, deadline, &progress, sim)?;
+    write_output(&results, sim.percent || stopped, opts, sim)
+}
+
+/// Replay the simulation at index `simulation` alone, and write it to the output file, or stdout
+/// if unset.
+///
+/// With --sample, the simulation is that sample of the run with --seed; otherwise it is that
+/// outcome of an exhaustive run, which must be one of the possible outcomes.
+fn inspect(
+    simulator: &Simulator,
+    simulation: usize,
+    opts: &Opts,
+    sim: &SimulateOpts,
+) -> Result<(), Error> {
+    let replay = match (sim.sample, sim.seed) {
+        (Some(_), Some(seed)) => simulator.replay_sampled(simulation, seed),
+        _ => match simulator.simulation_space() {
+            Some(space) if simulation >= space => {
+                return Err(Error::usage(format!(
+                    "--inspect {} is not one of the {} possible outcomes",
+                    simulation, space
+                )));
+            }
+            _ => simulator.replay_first(simulation),
+        },
+    };
+    write_replay(&replay, open_output(opts)?);
+    Ok(())
+}
+
+/// Write the outcome of each match of `replay`, marking those that were already played, and the
+/// final standings with each player's points and record.
+fn write_replay(replay: &Replay, mut w: Box<dyn std::io::Write>) {
+    let standings = &replay.standings;
+    writeln!(w, "simulation {}:", standings.simulation).unwrap();
+    writeln!(w, "  matches:").unwrap();
+    for match_ in &replay.matches {
+        let (won, lost) = match match_.outcome {
+            Outcome::Player2Wins(_) => (match_.games.1, match_.games.0),
+            _ => match_.games,
+        };
+        let result = match match_.outcome {
+            _ if match_.player2 == BYE => format!("{} has a bye", match_.player1),
+            _ if !match_.others.is_empty() => {
+                let mut players = vec![&match_.player1, &match_.player2];
+                players.extend(&match_.others);
+                let winner = match match_.outcome {
+                    Outcome::Player2Wins(_) => 1,
+                    Outcome::OtherWins(other) => 2 + other,
+                    _ => 0,
+                };
+                let winner = players.remove(winner);
+                let losers = players.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+                format!("{} won the pod over {}", winner, losers.join(", "))
+            }
+            Outcome::Player1Wins(_) => {
+                format!(
+                    "{} beat {} {}-{}",
+                    match_.player1, match_.player2, won, lost
+                )
+            }
+            Outcome::Player2Wins(_) => {
+                format!(
+                    "{} beat {} {}-{}",
+                    match_.player2, match_.player1, won, lost
+                )
+            }
+            Outcome::Draw => format!("{} drew with {}", match_.player1, match_.player2),
+            Outcome::OtherWins(_) => unreachable!("only a pod has further players"),
+        };
+        let played = if match_.played { " (played)" } else { "" };
+        writeln!(w, "    {}{}", result, played).unwrap();
+    }
+    writeln!(w, "  standings:").unwrap();
+    write_standings(standings, standings.players.len(), "    ", &mut w);
+    if standings.weight != 1.0 {
+        writeln!(w, "  weight: {}", standings.weight).unwrap();
+    }
+    w.flush().unwrap();
+}
+
+/// Write the first `count` players of `standings`, one per line after `indent`, with their rank,
+/// points, and record, as wins-losses, or wins-losses-draws for players with draws.
+fn write_standings(standings: &Standings, count: usize, indent: &str, w: &mut dyn std::io::Write) {
+    for (rank, standing) in standings.players.iter().take(count).enumerate() {
+        let draws = if standing.draws > 0 {
+            format!("-{}", standing.draws)
+        } else {
+            String::new()
+        };
+        writeln!(
+            w,
+            "{}{}. {}: {} points, {}-{}{}",
+            indent,
+            rank + 1,
+            standing.name,
+            standing.points,
+            standing.wins,
+            standing.losses,
+            draws
+        )
+        .unwrap();
+    }
+}
+
+/// Add the players loaded from the players file at `path` to `players`, handling players that
+/// are already in `players` as set by `policy`.
+///
+/// Merging a player adds up their wins, losses, draws, games, and opponent records, and lists the
+/// opponents of both rows; the rating is kept from the first row that has one. Returns an error
+/// for a duplicate player if `policy` is [`DuplicatePolicy::Error`].
+fn merge_players(
+    players: &mut HashMap<String, Player>,
+    file_players: HashMap<String, Player>,
+    policy: DuplicatePolicy,
+    path: &str,
+) -> Result<(), Error> {
+    // Go through the players in name order, so that the same files always report the same
+    // duplicate.
+    let mut file_players = file_players.into_iter().collect::<Vec<_>>();
+    file_players.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, player) in file_players {
+        let existing = match players.get_mut(&name) {
+            Some(existing) => existing,
+            None => {
+                players.insert(name, player);
+                continue;
+            }
+        };
+        match policy {
+            DuplicatePolicy::Error => {
+                return Err(Error::data(format!(
+                    "player '{}' in {} is also in an earlier players file; use --on-duplicate \
+                     merge or last to allow it",
+                    name,
+                    describe_path(path)
+                )));
+            }
+            DuplicatePolicy::Last => *existing = player,
+            DuplicatePolicy::Merge => {
+                existing.wins = existing.wins.saturating_add(player.wins);
+                existing.losses = existing.losses.saturating_add(player.losses);
+                existing.draws = existing.draws.saturating_add(player.draws);
+                existing.opp_wins = existing.opp_wins.saturating_add(player.opp_wins);
+                existing.opp_losses = existing.opp_losses.saturating_add(player.opp_losses);
+                existing.game_wins = existing.game_wins.saturating_add(player.game_wins);
+                existing.game_losses = existing.game_losses.saturating_add(player.game_losses);
+                existing.opp_game_wins =
+                    existing.opp_game_wins.saturating_add(player.opp_game_wins);
+                existing.opp_game_losses = existing
+                    .opp_game_losses
+                    .saturating_add(player.opp_game_losses);
+                existing.opponents.extend(player.opponents);
+                existing.rating = existing.rating.or(player.rating);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the names of the players of a --round-robin group from `spec`.
+///
+/// If `spec` is a file, it lists the names separated by commas or newlines; otherwise `spec` is
+/// the comma-separated list of names itself. Surrounding spaces and empty names are dropped.
+fn read_group(spec: &str) -> Result<Vec<String>, Error> {
+    let list = if std::path::Path::new(spec).is_file() {
+        std::fs::read_to_string(spec)
+            .map_err(|e| Error::io(format!("could not read {}: {}", spec, e)))?
+    } else {
+        spec.to_string()
+    };
+    Ok(list
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Most samples drawn by --target-error without --sample
+const DEFAULT_MAX_SAMPLES: usize = 10_000_000;
+
+/// Number of samples drawn by --target-error between checks of the margin of error
+const TARGET_ERROR_INTERVAL: usize = 10_000;
+
+/// the number of samples to draw for --sample, or at most for --target-error, or None for runs
+/// that are not sampled
+fn sample_count(sample: Option<usize>, target_error: Option<f64>) -> Option<usize> {
+    match (sample, target_error) {
+        (Some(samples), _) => Some(samples),
+        (None, Some(_)) => Some(DEFAULT_MAX_SAMPLES),
+        (None, None) => None,
+    }
+}
+
+/// Sample with the given seed until the 95% margin of error of each watched player's chance of a
+/// top finish is below `target` percentage points, `max_samples` have been drawn, or the
+/// `deadline` of --max-runtime has passed.
+///
+/// The watched players are those given with --player, or else every player. Reports the number
+/// of samples drawn on stderr.
+#[allow(clippy::too_many_arguments)]
+fn run_to_target(
+    simulator: &Simulator,
+    max_samples: usize,
+    seed: u64,
+    target: f64,
+    deadline: Option<Instant>,
+    progress: &Progress,
+    opts: &Opts,
+    sim: &SimulateOpts,
+) -> Results {
+    let top_k = sim.top_k.unwrap_or(opts.top_ranks());
+    let margin = |results: &Results| {
+        results
+            .players()
+            .filter(|p| sim.player.is_empty() || sim.player.contains(&p.name))
+            .filter_map(|p| {
+                let estimate = p.top_k_probability(top_k, results.total())?;
+                let (low, high) = p.top_k_confidence_interval(top_k, results.total())?;
+                Some(100.0 * f64::max(estimate - low, high - estimate))
+            })
+            .fold(0.0, f64::max)
+    };
+    progress.start(max_samples);
+    let results =
+        simulator.run_sampled_until(max_samples, seed, TARGET_ERROR_INTERVAL, |results| {
+            margin(results) < target || past(deadline)
+        });
+    progress.finish();
+    let samples = results.total() as usize;
+    if margin(&results) < target {
+        note!(
+            "reached a margin of error below {} points after {} samples",
+            target,
+            samples
+        );
+    } else if samples < max_samples && past(deadline) {
+        warn!(
+            "stopped after the --max-runtime at {} samples, with a margin of error of {:.*} \
+             points, above the target of {}",
+            samples,
+            sim.precision,
+            margin(&results),
+            target
+        );
+    } else {
+        warn!(
+            "stopped at {} samples with a margin of error of {:.*} points, above the \
+             target of {}; use --sample to allow more",
+            samples,
+            sim.precision,
+            margin(&results),
+            target
+        );
+    }
+    results
+}
+
+/// Number of simulations timed by the dryrun command to estimate the time of the whole run
+const DRY_RUN_CALIBRATION: usize = 1000;
+
+/// Report the size of the run that `dry` asks for, and estimate how long it would take by timing
+/// a few simulations.
+///
+/// Returns an error if there are too many outcomes to simulate exhaustively and neither --sample
+/// nor --simulation-count is given, as the run itself would.
+fn dry_run(
+    simulator: &Simulator,
+    players: usize,
+    loaded_matches: usize,
+    opts: &Opts,
+    dry: &DryRunOpts,
+) -> Result<(), Error> {
+    let match_count = simulator.match_count();
+    let space = simulator.simulation_space();
+    let mut w = open_output(opts)?;
+    writeln!(w, "players: {}", players).unwrap();
+    writeln!(
+        w,
+        "matches: {} to simulate, {} with a result",
+        match_count,
+        loaded_matches - match_count
+    )
+    .unwrap();
+    match space {
+        Some(space) => {
+            let radix = if opts.draws { 3 } else { 2 };
+            if (radix as usize).checked_pow(match_count as u32) == Some(space) {
+                writeln!(
+                    w,
+                    "possible outcomes: {} ({}^{})",
+                    space, radix, match_count
+                )
+                .unwrap();
+            } else {
+                writeln!(w, "possible outcomes: {}", space).unwrap();
+            }
+        }
+        None => writeln!(w, "possible outcomes: too many to count").unwrap(),
+    }
+
+    let samples = sample_count(dry.sample, dry.target_error);
+    let simulations = match (samples, space, dry.simulation_count) {
+        (Some(samples), _, _) => samples,
+        (None, Some(space), count) => std::cmp::min(space, count.unwrap_or(usize::MAX)),
+        (None, None, Some(count)) => count,
+        (None, None, None) => {
+            w.flush().unwrap();
+            return Err(Error::usage(format!(
+                "{} matches give too many possible outcomes to simulate exhaustively; use \
+                 --sample to run random simulations instead",
+                match_count
+            )));
+        }
+    };
+    let kind = if dry.target_error.is_some() {
+        "sampled until --target-error, at most"
+    } else if dry.sample.is_some() {
+        "sampled"
+    } else if Some(simulations) == space {
+        "exhaustive"
+    } else {
+        "partial"
+    };
+    writeln!(w, "simulations: {} ({})", simulations, kind).unwrap();
+
+    let calibration = std::cmp::min(simulations, DRY_RUN_CALIBRATION);
+    let start = Instant::now();
+    if samples.is_some() {
+        simulator.run_sampled(calibration, 0);
+    } else {
+        simulator.run_first(calibration);
+    }
+    let estimate = start
+        .elapsed()
+        .mul_f64(simulations as f64 / calibration as f64);
+    writeln!(w, "estimated time: {}", format_duration(estimate)).unwrap();
+    w.flush().unwrap();
+    Ok(())
+}
+
+/// Check that the `checkpoint` loaded from --resume is for a run of `simulations` simulations with
+/// the given seed.
+fn check_resumed(
+    checkpoint: Checkpoint,
+    simulations: usize,
+    seed: Option<u64>,
+    sim: &SimulateOpts,
+) -> Result<Checkpoint, Error> {
+    let describe = |simulations: usize, seed: Option<u64>| match seed {
+        Some(seed) => format!("{} samples with seed {}", simulations, seed),
+        None => format!("the first {} simulations", simulations),
+    };
+    if checkpoint.simulations() != simulations || checkpoint.seed() != seed {
+        return Err(Error::usage(format!(
+            "{} is for a run of {}, but this run is of {}",
+            describe_path(sim.resume.as_deref().unwrap_or_default()),
+            describe(checkpoint.simulations(), checkpoint.seed()),
+            describe(simulations, seed)
+        )));
+    }
+    Ok(checkpoint)
+}
+
+/// Number of simulations run between checks of the clock for --max-runtime
+const MAX_RUNTIME_INTERVAL: usize = 10_000;
+
+/// whether the `deadline` of --max-runtime, if any, has passed
+fn past(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Run the rest of the simulations of `checkpoint`, saving it to --checkpoint if set, until they
+/// are done or the `deadline` of --max-runtime has passed.
+///
+/// Returns the results with whether the run stopped early, which is reported on stderr with the
+/// share of the run that was covered. A checkpoint is also saved when the run stops early, so
+/// that it can be resumed.
+fn run_checkpointed(
+    simulator: &Simulator,
+    checkpoint: Checkpoint,
+    deadline: Option<Instant>,
+    progress: &Progress,
+    sim: &SimulateOpts,
+) -> Result<(Results, bool), Error> {
+    let interval = match (&sim.checkpoint, deadline) {
+        (Some(_), None) => sim.checkpoint_interval,
+        (Some(_), Some(_)) => sim.checkpoint_interval.min(MAX_RUNTIME_INTERVAL),
+        (None, Some(_)) => MAX_RUNTIME_INTERVAL,
+        (None, None) => usize::MAX,
+    };
+    let (simulations, seed) = (checkpoint.simulations(), checkpoint.seed());
+    let saved = Cell::new(checkpoint.next());
+    let ran = Cell::new(checkpoint.next());
+    let stopping = Cell::new(false);
+    let save = |checkpoint: &Checkpoint| {
+        // The clock is read once per batch, so that the last batch before stopping is saved.
+        stopping.set(past(deadline));
+        let next = checkpoint.next();
+        ran.set(next);
+        if let Some(path) = &sim.checkpoint {
+            if next - saved.get() >= sim.checkpoint_interval
+                || next == simulations
+                || stopping.get()
+            {
+                saved.set(next);
+                if let Err(e) = save_checkpoint(path, checkpoint) {
+                    warn!("could not write checkpoint {}: {}", path, e);
+                }
+            }
+        }
+    };
+    let done = |_: &Checkpoint| stopping.get();
+    progress.start(simulations - checkpoint.next());
+    let results = simulator
+        .resume_until(checkpoint, interval, save, done)
+        .map_err(|e| {
+            let path = sim.resume.as_deref().unwrap_or_default();
+            Error::usage(format!("{}: {}", describe_path(path), e))
+        })?;
+    progress.finish();
+    let stopped = ran.get() < simulations;
+    if stopped {
+        let covered = 100.0 * ran.get() as f64 / simulations as f64;
+        match (seed, simulator.simulation_space()) {
+            (Some(_), _) => note!(
+                "stopped after the --max-runtime at {} of {} samples ({:.*}% of the run)",
+                ran.get(),
+                simulations,
+                sim.precision,
+                covered
+            ),
+            (None, space) => {
+                let space = space.map_or(String::from("too many"), |space| space.to_string());
+                warn!(
+                    "stopped after the --max-runtime at {} of {} simulations ({:.*}% of the run, \
+                     out of {} possible outcomes); the first outcomes are not a random sample, so \
+                     use --sample with --max-runtime for an unbiased estimate",
+                    ran.get(),
+                    simulations,
+                    sim.precision,
+                    covered,
+                    space
+                );
+            }
+        }
+    }
+    Ok((results, stopped))
+}
+
+/// Write `checkpoint` to the file at `path`.
+///
+/// The checkpoint is written to a temporary file first, which then replaces the file at `path`,
+/// so that a crash while writing never leaves a corrupt checkpoint behind.
+fn save_checkpoint(path: &str, checkpoint: &Checkpoint) -> std::io::Result<()> {
+    let temp = format!("{}.tmp", path);
+    let file = std::fs::File::create(&temp)?;
+    checkpoint.write(&file)?;
+    file.sync_all()?;
+    std::fs::rename(&temp, path)
+}
+
+/// The filename that stands for stdin
+const STDIN: &str = "-";
+
+/// Open the `kind` file at `path`, such as the players file, or stdin if it is `-`.
+///
+/// A file whose name ends in `.gz` is decompressed as gzip while it is read, so invalid gzip data
+/// shows up as an error reading the file. Returns an error naming the file if it can't be opened.
+fn open_input(path: &str, kind: &str) -> Result<Box<dyn std::io::Read>, Error> {
+    if path == STDIN {
+        return Ok(Box::new(std::io::stdin()));
+    }
+    let file = std::fs::File::open(path)
+        .map_err(|e| Error::io(format!("could not open {} file '{}': {}", kind, path, e)))?;
+    if !path.ends_with(".gz") {
+        return Ok(Box::new(file));
+    }
+    Ok(Box::new(GzDecoder::new(file)))
+}
+
+/// Open the output file, or stdout if unset.
+///
+/// Output is buffered, since results are written in many small pieces. Returns an error if the
+/// output file can't be created.
+fn open_output(opts: &Opts) -> Result<Box<dyn std::io::Write>, Error> {
+    if let Some(file) = &opts.output {
+        create_output(file, "output")
+    } else {
+        Ok(Box::new(std::io::BufWriter::new(std::io::stdout())))
+    }
+}
+
+/// Create the `kind` file at `path`, such as the output file, for buffered writing.
+///
+/// Returns an error naming the file if it can't be created.
+fn create_output(path: &str, kind: &str) -> Result<Box<dyn std::io::Write>, Error> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| Error::io(format!("could not create {} file '{}': {}", kind, path, e)))?;
+    Ok(Box::new(std::io::BufWriter::new(file)))
+}
+
+/// Run the analyze command for the player named `name`, or return an error if there is no such
+/// player or too many outcomes to check.
+fn analyze_player(simulator: &Simulator, analyze: &AnalyzeOpts, opts: &Opts) -> Result<(), Error> {
+    let name = analyze.player.as_str();
+    if simulator.simulation_space().is_none() {
+        let check = if analyze.match_impact {
+            format!("the impact of each match on {}", name)
+        } else {
+            format!("whether {} controls their own destiny", name)
+        };
+        return Err(Error::usage(format!(
+            "{} matches give too many outcomes to check {}",
+            simulator.match_count(),
+            check
+        )));
+    }
+    if analyze.match_impact {
+        let impacts = simulator
+            .match_impact(name)
+            .ok_or_else(|| Error::usage(format!("unknown player '{}' to analyze", name)))?;
+        write_match_impact(name, &impacts, opts.top_ranks(), open_output(opts)?);
+        return Ok(());
+    }
+    let destiny = simulator
+        .destiny(name)
+        .ok_or_else(|| Error::usage(format!("unknown player '{}' to analyze", name)))?;
+    write_destiny(name, destiny, opts.top_ranks(), open_output(opts)?);
+    Ok(())
+}
+
+/// Write whether the player named `name` controls their own destiny, and how many outcomes of
+/// their matches guarantee them a top finish.
+fn write_destiny(name: &str, destiny: Destiny, top_ranks: usize, mut w: Box<dyn std::io::Write>) {
+    let controls = if destiny.favorable > 0 {
+        "controls"
+    } else {
+        "does not control"
+    };
+    writeln!(w, "{} {} their own destiny:", name, controls).unwrap();
+    writeln!(w, "  remaining matches: {}", destiny.matches).unwrap();
+    writeln!(
+        w,
+        "  outcomes that guarantee a top-{} finish: {}",
+        top_ranks, destiny.favorable
+    )
+    .unwrap();
+    writeln!(w, "  other outcomes: {}", destiny.unfavorable).unwrap();
+    w.flush().unwrap();
+}
+
+/// Write the chance of the player named `name` finishing in the top ranks with each outcome of
+/// each remaining match, in the given order, and the impact of the match: the spread of those
+/// chances in percentage points.
+fn write_match_impact(
+    name: &str,
+    impacts: &[MatchImpact],
+    top_ranks: usize,
+    mut w: Box<dyn std::io::Write>,
+) {
+    writeln!(
+        w,
+        "chance of a top-{} finish for {} by match outcome:",
+        top_ranks, name
+    )
+    .unwrap();
+    for impact in impacts {
+        let outcomes = [
+            (impact.player1_wins, format!("{} wins", impact.player1)),
+            (impact.player2_wins, format!("{} wins", impact.player2)),
+            (impact.draw, String::from("drawn")),
+        ];
+        let others = impact
+            .others
+            .iter()
+            .map(|(name, chance)| (*chance, format!("{} wins", name)));
+        let chances = outcomes
+            .iter()
+            .cloned()
+            .chain(others)
+            .filter_map(|(chance, outcome)| {
+                chance.map(|p| format!("{:.2}% if {}", 100.0 * p, outcome))
+            })
+            .collect::<Vec<_>>();
+        let others = impact
+            .others
+            .iter()
+            .map(|(name, _)| format!(" vs {}", name))
+            .collect::<String>();
+        writeln!(
+            w,
+            "  {} vs {}{}: {} (impact: {:.2})",
+            impact.player1,
+            impact.player2,
+            others,
+            chances.join(", "),
+            100.0 * impact.impact()
+        )
+        .unwrap();
+    }
+    w.flush().unwrap();
+}
+
+/// Write the placements of all placed players to the output file, or stdout if unset.
+///
+/// With --player, only the named players are written, whether they placed or not. With
+/// --eliminated, only the players who never placed are written, of the named players if any.
+///
+/// If `percent` is set, placements are written as percentages of the total weight of the
+/// simulations that were run. The same players are also written to the --summary-output and
+/// --detail-output files, if set, in the summary and rank-matrix formats.
+fn write_output(
+    results: &Results,
+    percent: bool,
+    opts: &Opts,
+    sim: &SimulateOpts,
+) -> Result<(), Error> {
+    let include_empty = sim.all || sim.include_empty;
+    let total = results.total();
+    if let Some(distinct) = results.distinct_standings() {
+        note!(
+            "{} distinct top-{} standings; the most common, with a chance of {:.*}%: {}",
+            distinct.count,
+            results.top_ranks(),
+            sim.precision,
+            100.0 * distinct.probability,
+            distinct.mode.join(", ")
+        );
+    }
+    if sim.eliminated && !results.is_exhaustive() {
+        warn!("not every outcome was simulated, so players who never placed may not be eliminated");
+    }
+    let top8 = results
+        .players()
+        .filter(|p| {
+            if sim.eliminated {
+                p.status(total) == Some(Status::Eliminated)
+                    && (sim.player.is_empty() || sim.player.contains(&p.name))
+            } else if sim.player.is_empty() {
+                include_empty || !p.placements.is_empty()
+            } else {
+                sim.player.contains(&p.name)
+            }
+        })
+        .collect::<Vec<_>>();
+    let order = if let Some(order) = sim.sort {
+        order
+    } else if sim.format == OutputFormat::RankMatrix {
+        PlayerOrder::ExpectedRank
+    } else if sim.all {
+        PlayerOrder::LikelyFinish
+    } else {
+        PlayerOrder::TopK
+    };
+
+    let output = open_output(opts)?;
+    let summary_output = sim
+        .summary_output
+        .as_deref()
+        .map(|path| create_output(path, "summary output"))
+        .transpose()?;
+    let detail_output = sim
+        .detail_output
+        .as_deref()
+        .map(|path| create_output(path, "detail output"))
+        .transpose()?;
+    let scale = if percent && total > 0.0 {
+        Scale::Percentages {
+            total,
+            precision: sim.precision,
+        }
+    } else {
+        Scale::Counts {
+            precision: sim.precision,
+        }
+    };
+    let report = Report {
+        order,
+        scale,
+        top_ranks: results.top_ranks(),
+        total,
+        precision: sim.precision,
+        top_k: sim.top_k.unwrap_or(opts.top_ranks()),
+        status: results.is_exhaustive(),
+        expected_rank: sim.expected_rank,
+        volatility: sim.volatility,
+        interval: sample_count(sim.sample, sim.target_error).is_some(),
+        raw: sim.raw,
+        // owo-colors leaves out the colors unless stdout is a terminal and NO_COLOR is unset.
+        color: sim.format == OutputFormat::Text && opts.output.is_none(),
+    };
+    if let Some(w) = summary_output {
+        write_results(top8.clone(), &report, OutputFormat::Summary, w);
+    }
+    if let Some(w) = detail_output {
+        write_results(top8.clone(), &report, OutputFormat::RankMatrix, w);
+    }
+    if sim.matrix {
+        write_matrix(top8, &report, results, output);
+    } else if sim.histogram {
+        write_histogram(top8, &report, output);
+    } else {
+        write_results(top8, &report, sim.format, output);
     }
+    Ok(())
 }
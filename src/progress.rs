@@ -0,0 +1,148 @@
+// Copyright 2020 Tim Kilbourn. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Progress reporting for long simulation runs.
+
+use {
+    indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle},
+    std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// Layout of the progress bar
+const BAR_TEMPLATE: &str = "[{bar:30}] {percent:>3}% {pos}/{len} ETA {eta_precise}";
+
+/// How many times a second the progress bar is redrawn at most
+const REDRAW_RATE: u8 = 10;
+
+/// Number of finished simulations between updates of the progress bar, so that most simulations
+/// don't need to touch it
+const REDRAW_CHECK: usize = 1024;
+
+/// Progress of a simulation run, reported on stderr.
+///
+/// On a terminal, progress is drawn as an indicatif bar with the percentage done and the estimated
+/// time remaining. Otherwise, a plain line is written every `interval` simulations.
+pub struct Progress {
+    interval: usize,
+    terminal: bool,
+    total: AtomicUsize,
+    finished: AtomicUsize,
+    state: Mutex<State>,
+}
+
+struct State {
+    start: Instant,
+    /// the progress bar of the current run, on a terminal
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// Create a progress reporter, drawing a bar if `terminal` is set.
+    pub fn new(interval: usize, terminal: bool) -> Progress {
+        Progress {
+            interval,
+            terminal,
+            total: AtomicUsize::new(0),
+            finished: AtomicUsize::new(0),
+            state: Mutex::new(State {
+                start: Instant::now(),
+                bar: None,
+            }),
+        }
+    }
+
+    /// Start reporting progress for a run of `total` simulations.
+    pub fn start(&self, total: usize) {
+        self.total.store(total, Ordering::Relaxed);
+        self.finished.store(0, Ordering::Relaxed);
+        let mut state = self.state.lock().unwrap();
+        state.start = Instant::now();
+        state.bar = self.terminal.then(|| {
+            let target = ProgressDrawTarget::stderr_with_hz(REDRAW_RATE);
+            let bar = ProgressBar::with_draw_target(Some(total as u64), target);
+            if let Ok(style) = ProgressStyle::with_template(BAR_TEMPLATE) {
+                bar.set_style(style.progress_chars("#-"));
+            }
+            bar
+        });
+    }
+
+    /// Record a finished simulation.
+    pub fn tick(&self) {
+        let finished = self.finished.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.terminal {
+            if !finished.is_multiple_of(REDRAW_CHECK) {
+                return;
+            }
+            // Skip the update if another thread is already updating the bar.
+            if let Ok(state) = self.state.try_lock() {
+                if let Some(bar) = &state.bar {
+                    bar.set_position(finished as u64);
+                }
+            }
+        } else if finished.is_multiple_of(self.interval) {
+            let total = self.total.load(Ordering::Relaxed);
+            let elapsed = self.state.lock().unwrap().start.elapsed();
+            eprintln!(
+                "progress: {}/{} simulations ({:.1}%), {} remaining",
+                finished,
+                total,
+                percent(finished, total),
+                format_duration(remaining(finished, total, elapsed))
+            );
+        }
+    }
+
+    /// Finish reporting progress for the current run.
+    ///
+    /// If the progress bar was updated, it is drawn once more with the final count; a run too
+    /// short for that leaves no bar behind.
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(bar) = state.bar.take() {
+            let finished = self.finished.load(Ordering::Relaxed);
+            if finished >= REDRAW_CHECK {
+                bar.set_position(finished as u64);
+                bar.abandon();
+            } else {
+                bar.finish_and_clear();
+            }
+        }
+    }
+}
+
+/// Compute `finished` as a percentage of `total`.
+fn percent(finished: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        finished as f64 / total as f64 * 100.0
+    }
+}
+
+/// Estimate the time remaining to finish `total` simulations, if `finished` took `elapsed`.
+fn remaining(finished: usize, total: usize, elapsed: Duration) -> Duration {
+    if finished == 0 {
+        return Duration::ZERO;
+    }
+    let left = total.saturating_sub(finished);
+    elapsed.mul_f64(left as f64 / finished as f64)
+}
+
+/// Format `duration` as hours, minutes, and seconds.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,222 @@
+use std::process::{Command, Output};
+
+/// run ccls in the fixtures directory with `args`
+fn ccls(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ccls"))
+        .args(args)
+        .current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .env_remove("RUST_LOG")
+        .output()
+        .expect("ccls should run")
+}
+
+/// run ccls on the fixture players and matches, counting the top 2 ranks, with `args` after the
+/// global options
+fn ccls_fixture(args: &[&str]) -> Output {
+    let global = ["-p", "players.csv", "-m", "matches.csv", "-t", "2"];
+    ccls(&[&global[..], args].concat())
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(
+        output.status.success(),
+        "ccls failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn simulate_text() {
+    assert_eq!(
+        stdout(&ccls_fixture(&["simulate"])),
+        "final players:
+  Alice: {1: 5, 2: 1} (top 2: 75.00%, status: alive)
+  Carol: {1: 1, 2: 3} (top 2: 50.00%, status: alive)
+  Dave: {1: 2} (top 2: 25.00%, status: alive)
+  Bob: {2: 2} (top 2: 25.00%, status: alive)
+  Eve: {2: 2} (top 2: 25.00%, status: alive)
+"
+    );
+}
+
+#[test]
+fn simulate_csv() {
+    assert_eq!(
+        stdout(&ccls_fixture(&["simulate", "--format", "csv"])),
+        "name,rank,count,top_k_percent,status
+Alice,1,5,75.00,alive
+Alice,2,1,75.00,alive
+Carol,1,1,50.00,alive
+Carol,2,3,50.00,alive
+Dave,1,2,25.00,alive
+Bob,2,2,25.00,alive
+Eve,2,2,25.00,alive
+"
+    );
+}
+
+#[test]
+fn simulate_json() {
+    let output = ccls_fixture(&["simulate", "--format", "json"]);
+    let json: serde_json::Value = serde_json::from_str(stdout(&output)).unwrap();
+    let players = json.as_array().unwrap();
+    let names: Vec<_> = players
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Alice", "Carol", "Dave", "Bob", "Eve"]);
+    assert_eq!(
+        players[0]["placements"],
+        serde_json::json!({"1": 5, "2": 1})
+    );
+    assert_eq!(players[0]["top_k_percent"], 75.0);
+    assert_eq!(players[0]["status"], "alive");
+}
+
+#[test]
+fn simulate_is_the_default_command() {
+    assert_eq!(
+        stdout(&ccls_fixture(&[])),
+        stdout(&ccls_fixture(&["simulate"]))
+    );
+}
+
+#[test]
+fn sampled_output_is_reproducible() {
+    for format in ["text", "csv", "json"] {
+        let args = ["simulate", "-s", "200", "--seed", "7", "--format", format];
+        let first = ccls_fixture(&args);
+        let second = ccls_fixture(&args);
+        assert_eq!(stdout(&first), stdout(&second), "{} output differs", format);
+    }
+}
+
+#[test]
+fn sampled_output_does_not_depend_on_threads() {
+    let run = |threads| {
+        let args = [
+            "-p",
+            "players.csv",
+            "-m",
+            "matches.csv",
+            "-t",
+            "2",
+            "-j",
+            threads,
+        ];
+        ccls(&[&args[..], &["simulate", "-s", "200", "--seed", "7"]].concat())
+    };
+    assert_eq!(stdout(&run("1")), stdout(&run("3")));
+}
+
+#[test]
+fn gzip_input() {
+    let gzip = ccls(&["-p", "players.csv.gz", "-m", "matches.csv", "-t", "2"]);
+    assert_eq!(stdout(&gzip), stdout(&ccls_fixture(&[])));
+
+    // Data that isn't gzip is invalid data, not an I/O error.
+    let output = ccls(&["-p", "players.csv", "-m", "not-gzip.csv.gz", "-t", "2"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(
+        stderr(&output),
+        "error: not-gzip.csv.gz: invalid gzip header\n"
+    );
+}
+
+#[test]
+fn json_input() {
+    for format in ["text", "csv"] {
+        let json = ccls(&[
+            "--input-format",
+            "json",
+            "-p",
+            "players.json",
+            "-m",
+            "matches.json",
+            "-t",
+            "2",
+            "simulate",
+            "--format",
+            format,
+        ]);
+        let csv = ccls_fixture(&["simulate", "--format", format]);
+        assert_eq!(stdout(&json), stdout(&csv), "{} output differs", format);
+    }
+
+    let output = ccls(&[
+        "--input-format",
+        "json",
+        "-p",
+        "invalid.json",
+        "-m",
+        "matches.json",
+        "-t",
+        "2",
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(
+        stderr(&output),
+        "error: invalid.json line 3: expected `,` or `}`\n"
+    );
+}
+
+#[test]
+fn dryrun() {
+    assert_eq!(
+        stdout(&ccls_fixture(&["dryrun"])),
+        "players: 5
+matches: 3 to simulate, 0 with a result
+possible outcomes: 8 (2^3)
+simulations: 8 (exhaustive)
+estimated time: 0:00:00
+"
+    );
+}
+
+#[test]
+fn analyze() {
+    assert_eq!(
+        stdout(&ccls_fixture(&["analyze", "Alice"])),
+        "Alice controls their own destiny:
+  remaining matches: 1
+  outcomes that guarantee a top-2 finish: 1
+  other outcomes: 1
+"
+    );
+}
+
+#[test]
+fn usage_error_exits_2() {
+    let output = ccls(&["-m", "matches.csv", "-t", "2"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr(&output), "error: --players is required\n");
+    assert!(output.stdout.is_empty());
+
+    let output = ccls_fixture(&["analyze", "Nobody"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("Nobody"));
+}
+
+#[test]
+fn io_error_exits_3() {
+    let output = ccls(&["-p", "missing.csv", "-m", "matches.csv", "-t", "2"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).starts_with("error: could not open players file 'missing.csv'"));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn data_error_exits_4() {
+    let output = ccls(&["-p", "truncated.csv", "-m", "matches.csv", "-t", "2"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(
+        stderr(&output),
+        "error: truncated.csv line 3: found 3 fields, but the header has 7; is the row truncated?\n"
+    );
+    assert!(output.stdout.is_empty());
+}